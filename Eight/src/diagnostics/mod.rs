@@ -0,0 +1,287 @@
+use crate::display::DisplayDefinition;
+use crate::solver;
+
+// Works out what's wrong with a line that can't be wired up. The faults we look for are a wire that's
+// stuck on or stuck off for the whole line and a single reading with one wire wrong
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    StuckOn,
+    StuckOff,
+    // The wire was wrong in just this one observation
+    Flaky { observation: usize },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suspect {
+    pub wire: usize,
+    pub fault: Fault,
+    // The observations that only read right once this fault is taken into account
+    pub explains: Vec<usize>,
+    // This suspect's share of all the observations explained by every suspect
+    pub confidence: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnosis {
+    pub contradictions: Vec<String>,
+    // Most likely first
+    pub suspects: Vec<Suspect>,
+}
+
+impl Diagnosis {
+    pub fn most_likely(&self) -> Option<&Suspect> {
+        self.suspects.first()
+    }
+}
+
+fn pattern_to_string(display: &DisplayDefinition, pattern: u32) -> String {
+    (0..display.num_segments).filter(|wire| pattern & (1 << wire) != 0).map(|wire| display.wire_name(wire)).collect()
+}
+
+// Things about the observations that can't be right no matter how the wires go
+fn find_contradictions(display: &DisplayDefinition, patterns: &[u32]) -> Vec<String> {
+    let mut contradictions = Vec::new();
+    for (observation, pattern) in patterns.iter().enumerate() {
+        if !display.symbols.iter().any(|(_, symbol)| symbol.count_ones() == pattern.count_ones()) {
+            contradictions.push(format!("{} (observation {}) lights {} wires and no symbol uses that many",
+                pattern_to_string(display, *pattern), observation + 1, pattern.count_ones()));
+        }
+    }
+    let mut distinct = patterns.to_vec();
+    distinct.sort_unstable();
+    distinct.dedup();
+    if distinct.len() > display.symbols.len() {
+        contradictions.push(format!("{} different patterns but a {} display only has {} symbols",
+            distinct.len(), display.name, display.symbols.len()));
+    }
+    // Two different patterns can't be the same symbol, so there can't be more of a size than symbols
+    for size in 1..=display.num_segments as u32 {
+        let symbols = display.symbols.iter().filter(|(_, s)| s.count_ones() == size).count();
+        let seen = distinct.iter().filter(|p| p.count_ones() == size).count();
+        if symbols > 0 && seen > symbols {
+            contradictions.push(format!("{} different patterns light {} wires but only {} symbols do",
+                seen, size, symbols));
+        }
+    }
+    contradictions
+}
+
+// Like the solver's search but one wire tells us nothing. Its segment is whatever the others leave
+// over and we can't say whether that segment should be lit, so patterns are only compared on the rest
+struct MaskedSearch<'a> {
+    display: &'a DisplayDefinition,
+    patterns: &'a [u32],
+    masked_wire: usize,
+    mapping: Vec<u8>,
+}
+
+impl MaskedSearch<'_> {
+    // Until every other wire is placed we just check nothing contradicts a symbol. After that the
+    // leftover segment is known and the rest of the symbol has to match exactly
+    fn fits(&self, assigned: u32, leftover: Option<u32>) -> bool {
+        self.patterns.iter().all(|pattern| {
+            let on = solver::apply(&self.mapping, pattern & assigned);
+            let off = solver::apply(&self.mapping, !pattern & assigned);
+            self.display.symbols.iter().any(|(_, symbol)| match leftover {
+                None => symbol & on == on && symbol & off == 0,
+                Some(segment) => symbol & !segment == on
+            })
+        })
+    }
+
+    fn run(&mut self, wire: usize, assigned: u32, used: u32) -> bool {
+        if wire == self.mapping.len() {
+            return true;
+        }
+        if wire == self.masked_wire {
+            return self.run(wire + 1, assigned, used);
+        }
+        for segment in 0..self.display.num_segments {
+            if used & (1 << segment) != 0 {
+                continue;
+            }
+            self.mapping[wire] = segment as u8;
+            let used = used | (1 << segment);
+            let assigned = assigned | (1 << wire);
+            let complete = assigned | (1 << self.masked_wire) == self.display.all_segments();
+            let leftover = if complete { Some(self.display.all_segments() & !used) } else { None };
+            if self.fits(assigned, leftover) && self.run(wire + 1, assigned, used) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+// A wiring that explains every pattern if we ignore what `wire` shows. The ignored wire gets the
+// segment the others leave over
+fn wiring_without_wire(display: &DisplayDefinition, patterns: &[u32], wire: usize) -> Option<Vec<u8>> {
+    let mut search = MaskedSearch {
+        display,
+        patterns,
+        masked_wire: wire,
+        mapping: vec![0; display.num_segments],
+    };
+    if !search.run(0, 0, 0) {
+        return None;
+    }
+    let used = search.mapping.iter().enumerate().filter(|(w, _)| *w != wire).fold(0u32, |used, (_, s)| used | (1 << s));
+    search.mapping[wire] = (display.all_segments() & !used).trailing_zeros() as u8;
+    Some(search.mapping)
+}
+
+// The observations a stuck wire gets wrong. Those are the ones where every symbol the other wires
+// could be showing disagrees with what the stuck wire reads
+fn stuck_explains(display: &DisplayDefinition, patterns: &[u32], mapping: &[u8], wire: usize, fault: Fault) -> Vec<usize> {
+    let segment = 1u32 << mapping[wire];
+    let reads_lit = fault == Fault::StuckOn;
+    patterns.iter().enumerate()
+        .filter(|(_, pattern)| {
+            let on = solver::apply(mapping, *pattern & !(1 << wire));
+            display.symbols.iter()
+                .filter(|(_, symbol)| symbol & !segment == on)
+                .all(|(_, symbol)| (symbol & segment != 0) != reads_lit)
+        })
+        .map(|(observation, _)| observation)
+        .collect()
+}
+
+// If everything but one observation fits, the wire that's off by one in it is the flaky one. The
+// observation has to be exactly one segment away from a symbol under a wiring that fits the rest.
+// There can be a few symbols that close, and the ones the rest of the line never shows are the better
+// bet since a line normally shows every symbol
+fn flaky_wires(display: &DisplayDefinition, patterns: &[u32], observation: usize, mapping: &[u8]) -> Vec<usize> {
+    let rest: Vec<u32> = patterns.iter().enumerate().filter(|(i, _)| *i != observation).map(|(_, p)| *p).collect();
+    let segments = solver::apply(mapping, patterns[observation]);
+    let shown: Vec<u32> = rest.iter().map(|p| solver::apply(mapping, *p)).collect();
+    let close: Vec<u32> = display.symbols.iter()
+        .map(|(_, symbol)| *symbol)
+        .filter(|symbol| (symbol ^ segments).count_ones() == 1)
+        .collect();
+    let unseen: Vec<u32> = close.iter().copied().filter(|symbol| !shown.contains(symbol)).collect();
+    let candidates = if unseen.is_empty() { close } else { unseen };
+    candidates.iter()
+        .filter_map(|symbol| {
+            let segment = (symbol ^ segments).trailing_zeros() as u8;
+            mapping.iter().position(|s| *s == segment)
+        })
+        .collect()
+}
+
+// A fault that accounts for more of the line is the better bet, so suspects are ranked by how many
+// observations they explain. When that ties, the one explaining more of the observations the rest of
+// the line can't stand is ahead, then stuck wires come before flaky ones
+pub fn diagnose(display: &DisplayDefinition, patterns: &[u32]) -> Diagnosis {
+    let contradictions = find_contradictions(display, patterns);
+    // An observation is contradictory when the line can be wired up without it
+    let without: Vec<Option<Vec<u8>>> = (0..patterns.len())
+        .map(|observation| {
+            let rest: Vec<u32> = patterns.iter().enumerate().filter(|(i, _)| *i != observation).map(|(_, p)| *p).collect();
+            solver::find_mappings(display, &rest, 1).pop()
+        })
+        .collect();
+    let mut suspects: Vec<Suspect> = Vec::new();
+    for wire in 0..display.num_segments {
+        // A stuck wire reads the same in every pattern
+        let lit = patterns.iter().filter(|p| *p & (1 << wire) != 0).count();
+        let fault = if lit == patterns.len() {
+            Fault::StuckOn
+        } else if lit == 0 {
+            Fault::StuckOff
+        } else {
+            continue;
+        };
+        if let Some(mapping) = wiring_without_wire(display, patterns, wire) {
+            let explains = stuck_explains(display, patterns, &mapping, wire, fault);
+            suspects.push(Suspect { wire, fault, explains, confidence: 0.0 });
+        }
+    }
+    for (observation, mapping) in without.iter().enumerate() {
+        let Some(mapping) = mapping else { continue };
+        for wire in flaky_wires(display, patterns, observation, mapping) {
+            let fault = Fault::Flaky { observation };
+            if !suspects.iter().any(|s| s.wire == wire && s.fault == fault) {
+                suspects.push(Suspect { wire, fault, explains: vec![observation], confidence: 0.0 });
+            }
+        }
+    }
+    let total: usize = suspects.iter().map(|s| s.explains.len()).sum();
+    for suspect in suspects.iter_mut() {
+        suspect.confidence = suspect.explains.len() as f64 / total.max(1) as f64;
+    }
+    let contradictory = |suspect: &Suspect| suspect.explains.iter().filter(|o| without[**o].is_some()).count();
+    // The sort is stable so stuck wires stay ahead of flaky ones on a tie
+    suspects.sort_by(|a, b| b.explains.len().cmp(&a.explains.len()).then(contradictory(b).cmp(&contradictory(a))));
+    Diagnosis { contradictions, suspects }
+}
+
+pub fn describe(display: &DisplayDefinition, patterns: &[u32], suspect: &Suspect) -> String {
+    let wire = display.wire_name(suspect.wire);
+    let what = match suspect.fault {
+        Fault::StuckOn => format!("wire {} is stuck on", wire),
+        Fault::StuckOff => format!("wire {} is stuck off", wire),
+        Fault::Flaky { observation } => format!("wire {} is wrong in {} (observation {})",
+            wire, pattern_to_string(display, patterns[observation]), observation + 1)
+    };
+    format!("{} (explains {} of {} observations, {:.0}% confidence)",
+        what, suspect.explains.len(), patterns.len(), suspect.confidence * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The AoC sample line's wiring, mapping[wire] = segment
+    const WIRING: [u8; 7] = [2, 5, 6, 0, 1, 3, 4];
+
+    fn observed(display: &DisplayDefinition, fault: impl Fn(usize, u32) -> u32) -> Vec<u32> {
+        display.symbols.iter().enumerate().map(|(observation, (_, segments))| {
+            let pattern = (0..WIRING.len()).filter(|w| segments & (1 << WIRING[*w]) != 0).fold(0, |p, w| p | (1 << w));
+            fault(observation, pattern)
+        }).collect()
+    }
+
+    fn wire_for(segment: u8) -> usize {
+        WIRING.iter().position(|s| *s == segment).unwrap()
+    }
+
+    #[test]
+    fn names_a_stuck_on_wire() {
+        let display = DisplayDefinition::seven_segment();
+        // Bottom left is off in 1, 3, 4, 5, 7 and 9. With it stuck on 5 and 9 look like 6 and 8, so
+        // only the other four are wrong for certain
+        let wire = wire_for(4);
+        let patterns = observed(&display, |_, p| p | (1 << wire));
+        assert_eq!(solver::find_mappings(&display, &patterns, 1), Vec::<Vec<u8>>::new());
+        let diagnosis = diagnose(&display, &patterns);
+        let suspect = diagnosis.most_likely().unwrap();
+        assert_eq!((suspect.wire, suspect.fault), (wire, Fault::StuckOn));
+        assert_eq!(suspect.explains, vec![1, 3, 4, 7]);
+        assert!(diagnosis.suspects.iter().skip(1).all(|s| s.confidence < suspect.confidence));
+    }
+
+    #[test]
+    fn names_a_stuck_off_wire() {
+        let display = DisplayDefinition::seven_segment();
+        for segment in 0..7 {
+            let wire = wire_for(segment);
+            let patterns = observed(&display, |_, p| p & !(1 << wire));
+            let diagnosis = diagnose(&display, &patterns);
+            let suspect = diagnosis.most_likely().unwrap();
+            assert_eq!((suspect.wire, suspect.fault), (wire, Fault::StuckOff), "segment {}", segment);
+            assert!(suspect.explains.len() > 1, "segment {}", segment);
+        }
+    }
+
+    #[test]
+    fn names_a_flaky_wire() {
+        let display = DisplayDefinition::seven_segment();
+        // Bottom left drops out of the 0, which leaves five segments that aren't any digit
+        let wire = wire_for(4);
+        let patterns = observed(&display, |o, p| if o == 0 { p & !(1 << wire) } else { p });
+        let suspect = diagnose(&display, &patterns).most_likely().cloned().unwrap();
+        assert_eq!((suspect.wire, suspect.fault), (wire, Fault::Flaky { observation: 0 }));
+        assert_eq!(suspect.explains, vec![0]);
+    }
+}
@@ -0,0 +1,194 @@
+// A display is a set of segments and the symbols it can show. Each symbol is the bitmask of the
+// segments it lights. Wires are named with letters from 'a' so a 16 segment display uses a-p
+
+pub struct DisplayDefinition {
+    pub name: &'static str,
+    pub num_segments: usize,
+    pub symbols: Vec<(char, u32)>,
+}
+
+fn mask(segments: &[u32]) -> u32 {
+    segments.iter().fold(0, |m, s| m | (1 << s))
+}
+
+impl DisplayDefinition {
+    pub fn new(name: &'static str, num_segments: usize, symbols: Vec<(char, u32)>) -> Result<DisplayDefinition, String> {
+        if num_segments == 0 || num_segments > 26 {
+            return Err(format!("{} needs between 1 and 26 segments", name));
+        }
+        for (i, (symbol, segments)) in symbols.iter().enumerate() {
+            if *segments >> num_segments != 0 {
+                return Err(format!("{} uses a segment {} doesn't have", symbol, name));
+            }
+            if let Some((other, _)) = symbols[..i].iter().find(|(_, s)| s == segments) {
+                return Err(format!("{} and {} look the same on {}", other, symbol, name));
+            }
+        }
+        Ok(DisplayDefinition { name, num_segments, symbols })
+    }
+
+    // The AoC lettering. a top, b top left, c top right, d middle, e bottom left, f bottom right, g bottom
+    pub fn seven_segment() -> DisplayDefinition {
+        let symbols = vec![
+            ('0', mask(&[0, 1, 2, 4, 5, 6])),
+            ('1', mask(&[2, 5])),
+            ('2', mask(&[0, 2, 3, 4, 6])),
+            ('3', mask(&[0, 2, 3, 5, 6])),
+            ('4', mask(&[1, 2, 3, 5])),
+            ('5', mask(&[0, 1, 3, 5, 6])),
+            ('6', mask(&[0, 1, 3, 4, 5, 6])),
+            ('7', mask(&[0, 2, 5])),
+            ('8', mask(&[0, 1, 2, 3, 4, 5, 6])),
+            ('9', mask(&[0, 1, 2, 3, 5, 6])),
+        ];
+        DisplayDefinition::new("seven segment", 7, symbols).expect("The seven segment digits are all different")
+    }
+
+    // Seven segments plus A b C d E F
+    pub fn seven_segment_hex() -> DisplayDefinition {
+        let mut symbols = DisplayDefinition::seven_segment().symbols;
+        symbols.extend_from_slice(&[
+            ('A', mask(&[0, 1, 2, 3, 4, 5])),
+            ('B', mask(&[1, 3, 4, 5, 6])),
+            ('C', mask(&[0, 1, 4, 6])),
+            ('D', mask(&[2, 3, 4, 5, 6])),
+            ('E', mask(&[0, 1, 3, 4, 6])),
+            ('F', mask(&[0, 1, 3, 4])),
+        ]);
+        DisplayDefinition::new("seven segment hex", 7, symbols).expect("The hex digits are all different")
+    }
+
+    // Segments are 0 top, 1 top right, 2 bottom right, 3 bottom, 4 bottom left, 5 top left,
+    // 6 middle left, 7 middle right, 8 top left diagonal, 9 top vertical, 10 top right diagonal,
+    // 11 bottom left diagonal, 12 bottom vertical, 13 bottom right diagonal
+    pub fn fourteen_segment() -> DisplayDefinition {
+        DisplayDefinition::new("fourteen segment", 14, fourteen_segment_font()).expect("The fourteen segment font is all different")
+    }
+
+    // Fourteen segments with the top and bottom bars split in two. 0 and 3 become the left halves and
+    // 14 and 15 are the right halves
+    pub fn sixteen_segment() -> DisplayDefinition {
+        let symbols = fourteen_segment_font().into_iter().map(|(symbol, segments)| {
+            let mut split = segments;
+            if segments & (1 << 0) != 0 {
+                split |= 1 << 14;
+            }
+            if segments & (1 << 3) != 0 {
+                split |= 1 << 15;
+            }
+            (symbol, split)
+        }).collect();
+        DisplayDefinition::new("sixteen segment", 16, symbols).expect("The sixteen segment font is all different")
+    }
+
+    pub fn by_name(name: &str) -> Option<DisplayDefinition> {
+        match name {
+            "seven" => Some(DisplayDefinition::seven_segment()),
+            "hex" => Some(DisplayDefinition::seven_segment_hex()),
+            "fourteen" => Some(DisplayDefinition::fourteen_segment()),
+            "sixteen" => Some(DisplayDefinition::sixteen_segment()),
+            _ => None
+        }
+    }
+
+    pub fn all_segments(&self) -> u32 {
+        (1 << self.num_segments) - 1
+    }
+
+    pub fn wire_name(&self, wire: usize) -> char {
+        (b'a' + wire as u8) as char
+    }
+
+    pub fn pattern_from_str(&self, pattern: &str) -> Option<u32> {
+        let mut mask = 0u32;
+        for c in pattern.bytes() {
+            if !c.is_ascii_lowercase() || (c - b'a') as usize >= self.num_segments {
+                return None;
+            }
+            mask |= 1 << (c - b'a');
+        }
+        Some(mask)
+    }
+
+    // Symbols with a number of segments no other symbol has, so they can be picked out by size alone
+    pub fn uniquely_sized(&self) -> Vec<(char, u32)> {
+        self.symbols.iter()
+            .filter(|(_, segments)| self.symbols.iter().filter(|(_, other)| other.count_ones() == segments.count_ones()).count() == 1)
+            .copied()
+            .collect()
+    }
+
+    // Displays that show the digits of a base in order read their outputs as numbers in that base, so
+    // the hex display counts in 16s. The fourteen and sixteen segment fonts are letters, not base 36
+    pub fn radix(&self) -> Option<u32> {
+        let digits = "0123456789ABCDEF";
+        let in_order = self.symbols.iter().map(|(symbol, _)| *symbol).eq(digits.chars().take(self.symbols.len()));
+        if self.symbols.len() >= 2 && self.symbols.len() <= digits.len() && in_order {
+            Some(self.symbols.len() as u32)
+        } else {
+            None
+        }
+    }
+
+    pub fn symbol_for(&self, segments: u32) -> Option<char> {
+        self.symbols.iter().find(|(_, s)| *s == segments).map(|(symbol, _)| *symbol)
+    }
+}
+
+fn fourteen_segment_font() -> Vec<(char, u32)> {
+    vec![
+        ('0', mask(&[0, 1, 2, 3, 4, 5, 10, 11])),
+        ('1', mask(&[1, 2])),
+        ('2', mask(&[0, 1, 3, 4, 6, 7])),
+        ('3', mask(&[0, 1, 2, 3, 7])),
+        ('4', mask(&[1, 2, 5, 6, 7])),
+        ('5', mask(&[0, 2, 3, 5, 6, 7])),
+        ('6', mask(&[0, 2, 3, 4, 5, 6, 7])),
+        ('7', mask(&[0, 1, 2])),
+        ('8', mask(&[0, 1, 2, 3, 4, 5, 6, 7])),
+        ('9', mask(&[0, 1, 2, 3, 5, 6, 7])),
+        ('A', mask(&[0, 1, 2, 4, 5, 6, 7])),
+        ('B', mask(&[0, 1, 2, 3, 7, 9, 12])),
+        ('C', mask(&[0, 3, 4, 5])),
+        ('D', mask(&[0, 1, 2, 3, 9, 12])),
+        ('E', mask(&[0, 3, 4, 5, 6])),
+        ('F', mask(&[0, 4, 5, 6])),
+        ('G', mask(&[0, 2, 3, 4, 5, 7])),
+        ('H', mask(&[1, 2, 4, 5, 6, 7])),
+        ('I', mask(&[0, 3, 9, 12])),
+        ('J', mask(&[1, 2, 3, 4])),
+        ('K', mask(&[4, 5, 6, 10, 13])),
+        ('L', mask(&[3, 4, 5])),
+        ('M', mask(&[1, 2, 4, 5, 8, 10])),
+        ('N', mask(&[1, 2, 4, 5, 8, 13])),
+        ('O', mask(&[0, 1, 2, 3, 4, 5])),
+        ('P', mask(&[0, 1, 4, 5, 6, 7])),
+        ('Q', mask(&[0, 1, 2, 3, 4, 5, 13])),
+        ('R', mask(&[0, 1, 4, 5, 6, 7, 13])),
+        ('S', mask(&[0, 2, 3, 7, 8])),
+        ('T', mask(&[0, 9, 12])),
+        ('U', mask(&[1, 2, 3, 4, 5])),
+        ('V', mask(&[4, 5, 10, 11])),
+        ('W', mask(&[1, 2, 4, 5, 11, 13])),
+        ('X', mask(&[8, 10, 11, 13])),
+        ('Y', mask(&[8, 10, 12])),
+        ('Z', mask(&[0, 3, 10, 11])),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radix_comes_from_the_digits_shown() {
+        assert_eq!(DisplayDefinition::seven_segment().radix(), Some(10));
+        assert_eq!(DisplayDefinition::seven_segment_hex().radix(), Some(16));
+        assert_eq!(DisplayDefinition::fourteen_segment().radix(), None);
+        assert_eq!(DisplayDefinition::sixteen_segment().radix(), None);
+        let octal = DisplayDefinition::seven_segment().symbols.into_iter().take(8).collect();
+        assert_eq!(DisplayDefinition::new("octal", 7, octal).unwrap().radix(), Some(8));
+        let shuffled = DisplayDefinition::seven_segment().symbols.into_iter().rev().collect();
+        assert_eq!(DisplayDefinition::new("backwards", 7, shuffled).unwrap().radix(), None);
+    }
+}
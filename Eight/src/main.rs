@@ -1,132 +1,219 @@
-use std::collections::{HashMap, HashSet};
+use crate::display::DisplayDefinition;
+use crate::solver::Solution;
 
+mod diagnostics;
+mod display;
+mod solver;
+
+// Patterns are bitmasks with bit n set when wire n is lit, so the order of the letters doesn't matter.
+// The inputs come first and then the outputs, all in one Vec
 struct Problem {
-    input_values: Vec<String>,
-    output_values: Vec<String>
+    patterns: Vec<u32>,
+    num_inputs: usize,
+    // Anything that uses a wire the display doesn't have. A line with any of these can't be wired up
+    unreadable: Vec<String>,
 }
 
-fn read_input(filename: &str) -> Vec<Problem>{
+fn parse_section(display: &DisplayDefinition, section: &str, patterns: &mut Vec<u32>, unreadable: &mut Vec<String>) {
+    for segment in section.split_ascii_whitespace() {
+        match display.pattern_from_str(segment) {
+            None => unreadable.push(segment.to_string()),
+            Some(pattern) => patterns.push(pattern)
+        }
+    }
+}
+
+fn read_input(filename: &str, display: &DisplayDefinition) -> Vec<Problem>{
     let file_contents = std::fs::read_to_string(filename).expect("Couldn't open the file");
     let mut problems = Vec::new();
     for line in file_contents.lines() {
-        let sections: Vec<&str> = line.split("|").collect();
-        let mut problem = Problem {input_values: Vec::new(), output_values: Vec::new()};
-        match sections.first() {
-            None => panic!("Didn't have the input section for the problem"),
-            Some(input_section) => {
-                for segment in input_section.split(" ") {
-                    if segment.len() == 0 {
-                        continue;
-                    }
-                    problem.input_values.push(segment.to_string());
-                }
-            }
-        }
-        match sections.get(1) {
+        let (input_section, output_section) = match line.split_once('|') {
             None => panic!("Didn't have the output section for the problem"),
-            Some(input_section) => {
-                for segment in input_section.split(" ") {
-                    if segment.len() == 0 {
-                        continue;
-                    }
-                    problem.output_values.push(segment.to_string());
-                }
-            }
-        }
-        problems.push(problem);
+            Some(sections) => sections
+        };
+        // Ten digits and four outputs is the usual line
+        let mut patterns = Vec::with_capacity(14);
+        let mut unreadable = Vec::new();
+        parse_section(display, input_section, &mut patterns, &mut unreadable);
+        let num_inputs = patterns.len();
+        parse_section(display, output_section, &mut patterns, &mut unreadable);
+        problems.push(Problem { patterns, num_inputs, unreadable });
     }
     problems
 }
 
-fn num_match(a: &String, b: &String) -> usize {
-    let mut matching_chars = 0;
-    for a_char in a.chars() {
-        if b.contains(a_char) {
-            matching_chars += 1;
-        }
-    }
-    matching_chars
-}
+// How many segments each digit shares with 1 and with 4, by digit. (lit, with 1, with 4) picks out
+// exactly one digit on a standard display
+const DIGIT_SIGNATURES: [(u32, u32, u32); 10] = [
+    (6, 2, 3),
+    (2, 2, 2),
+    (5, 1, 2),
+    (5, 2, 3),
+    (4, 2, 4),
+    (5, 1, 3),
+    (6, 1, 3),
+    (3, 2, 2),
+    (7, 2, 4),
+    (6, 2, 4),
+];
 
 impl Problem {
-    fn count_unique_nums_output(&self) -> usize {
-        let mut count:usize = 0;
-        for output_value in &self.output_values {
-            match output_value.len() {
-                2 | 3 | 4 | 7  => count += 1,
-                _ => {
-                    //do nothing here
-                }
-            }
-        }
-        count
+    fn output_values(&self) -> &[u32] {
+        &self.patterns[self.num_inputs..]
     }
 
-    fn decode(&self) -> u32 {
-        let mut known_examples = HashMap::new();
-        for value in &self.input_values {
-            match value.len() {
-                // We only care about these two because with these we can figure out the others
-                2 => {
-                    known_examples.insert(1, value.clone());
-                },
-                4 => {
-                    known_examples.insert(4, value.clone());
-                },
-                _ => {}
-            }
-            if known_examples.len() == 2 {
-                break;
+    // Outputs that can only be one symbol because nothing else lights that many segments
+    fn count_unique_nums_output(&self, uniquely_sized: &[(char, u32)]) -> usize {
+        self.output_values().iter()
+            .filter(|pattern| uniquely_sized.iter().any(|(_, segments)| segments.count_ones() == pattern.count_ones()))
+            .count()
+    }
+
+    // The quick way for the normal seven segment digits. Once we've seen 1 and 4 every digit can be
+    // told apart by how much it overlaps them. Then each segment is lit by a different set of digits,
+    // which gives the wiring, and every pattern has to come out as its digit through that wiring. None
+    // if any of that doesn't work out, the line might still be fine but it needs the proper solver
+    fn decode_seven_segment(&self, display: &DisplayDefinition) -> Option<u64> {
+        let one = *self.patterns.iter().find(|pattern| pattern.count_ones() == 2)?;
+        let four = *self.patterns.iter().find(|pattern| pattern.count_ones() == 4)?;
+        if one & !four != 0 {
+            return None;
+        }
+        let mut seen = [0u32; 10];
+        let mut digits = Vec::with_capacity(self.patterns.len());
+        for pattern in self.patterns.iter() {
+            let signature = (pattern.count_ones(), (pattern & one).count_ones(), (pattern & four).count_ones());
+            let digit = DIGIT_SIGNATURES.iter().position(|s| *s == signature)?;
+            // Two different patterns can't both be the same digit
+            if seen[digit] != 0 && seen[digit] != *pattern {
+                return None;
             }
+            seen[digit] = *pattern;
+            digits.push(digit);
         }
-        if known_examples.len() != 2 {
-            panic!("We can't solve this :(");
+        if seen.contains(&0) {
+            return None;
         }
-        let mut decoded = Vec::new();
-        for out_val in self.output_values.iter().rev() {
-            match out_val.len() {
-                2 => decoded.push(1),
-                3 => decoded.push(7),
-                4 => decoded.push(4),
-                5 => {
-                    if num_match(&out_val, &known_examples[&4]) == 2 {
-                        decoded.push(2);
-                    } else if num_match(&out_val, &known_examples[&1]) == 1 {
-                        decoded.push(5);
-                    } else {
-                        decoded.push(3);
-                    }
-                }
-                6 => {
-                    if num_match(&out_val, &known_examples[&1]) == 1 {
-                        decoded.push(6);
-                    } else if num_match(&out_val, &known_examples[&4]) == 3 {
-                        decoded.push(0);
-                    } else {
-                        decoded.push(9);
-                    }
-                }
-                7 => decoded.push(8),
-                _ => {}
-            }
+        // Which digits light a wire or a segment, as a bitmask of digits
+        let lit_by = |masks: &[u32], bit: usize| -> u32 {
+            masks.iter().enumerate().filter(|(_, m)| *m & (1 << bit) != 0).fold(0, |d, (digit, _)| d | (1 << digit))
+        };
+        let symbols: Vec<u32> = display.symbols.iter().map(|(_, segments)| *segments).collect();
+        let mut mapping = Vec::with_capacity(display.num_segments);
+        for wire in 0..display.num_segments {
+            let digits_on_wire = lit_by(&seen, wire);
+            let segment = (0..display.num_segments).find(|segment| lit_by(&symbols, *segment) == digits_on_wire)?;
+            mapping.push(segment as u8);
+        }
+        if self.patterns.iter().zip(digits.iter()).any(|(pattern, digit)| solver::apply(&mapping, *pattern) != symbols[*digit]) {
+            return None;
         }
-        let mut decoded_output:u32 = 0;
-        for (i, decoded_value) in decoded.iter().enumerate() {
-            decoded_output += *decoded_value as u32 * (10u32.pow(i as u32));
+        let mut value = 0;
+        for digit in digits[self.num_inputs..].iter() {
+            value = value * 10 + *digit as u64;
         }
-        decoded_output
+        Some(value)
     }
 
+    fn decode(&self, display: &DisplayDefinition) -> Solution {
+        if !self.unreadable.is_empty() {
+            return Solution::Unsolvable;
+        }
+        solver::solve(display, &self.patterns, self.output_values())
+    }
 }
 
+// "1, 4, 7 or 8"
+fn or_list(symbols: &[(char, u32)]) -> String {
+    match symbols.split_last() {
+        None => "nothing".to_string(),
+        Some(((last, _), [])) => last.to_string(),
+        Some(((last, _), rest)) => {
+            let rest: Vec<String> = rest.iter().map(|(symbol, _)| symbol.to_string()).collect();
+            format!("{} or {}", rest.join(", "), last)
+        }
+    }
+}
+
+fn print_diagnosis(display: &DisplayDefinition, problem: &Problem) {
+    if !problem.unreadable.is_empty() {
+        println!("  {} isn't a {} pattern", problem.unreadable.join(" and "), display.name);
+        return;
+    }
+    let patterns = problem.patterns.as_slice();
+    let diagnosis = diagnostics::diagnose(display, patterns);
+    for contradiction in diagnosis.contradictions.iter() {
+        println!("  {}", contradiction);
+    }
+    match diagnosis.most_likely() {
+        None => println!("  and no single bad wire explains it"),
+        Some(suspect) => {
+            println!("  most likely {}", diagnostics::describe(display, patterns, suspect));
+            for suspect in diagnosis.suspects.iter().skip(1).take(4) {
+                println!("  or {}", diagnostics::describe(display, patterns, suspect));
+            }
+        }
+    }
+}
 
 fn main() {
-    let problems = read_input("input");
+    // Eight [input] [seven|hex|fourteen|sixteen] [diagnose]
+    let args: Vec<String> = std::env::args().collect();
+    let display = match DisplayDefinition::by_name(args.get(2).map(|d| d.as_str()).unwrap_or("seven")) {
+        None => panic!("Don't know that display"),
+        Some(display) => display
+    };
+    let problems = read_input(args.get(1).map(|f| f.as_str()).unwrap_or("input"), &display);
+    let diagnose = args.get(3).map(|a| a == "diagnose").unwrap_or(false);
+    let standard_digits = display.symbols == DisplayDefinition::seven_segment().symbols;
+    let uniquely_sized = display.uniquely_sized();
     let mut count = 0;
     let mut output_sum = 0;
-    for problem in &problems {
-        count += problem.count_unique_nums_output();
-        output_sum += problem.decode();
+    // Outputs are numbers in the display's base. Anything that isn't gets printed instead of added up
+    let radix = display.radix();
+    let read_output = |line: usize, decoded: &str| match radix.map(|radix| u64::from_str_radix(decoded, radix)) {
+        Some(Ok(value)) => value,
+        _ => {
+            println!("Line {} reads {}", line + 1, decoded);
+            0
+        }
+    };
+    for (line, problem) in problems.iter().enumerate() {
+        count += problem.count_unique_nums_output(&uniquely_sized);
+        if standard_digits && !diagnose && problem.unreadable.is_empty() {
+            if let Some(value) = problem.decode_seven_segment(&display) {
+                output_sum += value;
+                continue;
+            }
+        }
+        match problem.decode(&display) {
+            Solution::Solved { decoded, .. } => output_sum += read_output(line, decoded.as_str()),
+            Solution::Ambiguous { mappings, decoded } => {
+                println!("Line {} has {} possible wirings:", line + 1, mappings.len());
+                for mapping in mappings.iter().take(5) {
+                    println!("  {}", solver::mapping_to_string(&display, mapping));
+                }
+                if mappings.len() > 5 {
+                    println!("  ...");
+                }
+                match decoded {
+                    None => println!("  and they don't agree on the output"),
+                    Some(decoded) => {
+                        println!("  but they all read the output as {}", decoded);
+                        output_sum += read_output(line, decoded.as_str());
+                    }
+                }
+            }
+            Solution::Unsolvable => {
+                println!("Line {} can't be wired up as a {} display", line + 1, display.name);
+                if diagnose {
+                    print_diagnosis(&display, problem);
+                }
+            }
+        }
+    }
+    match radix {
+        Some(_) => println!("There are {} instances of {}. Output sum is {}", count, or_list(&uniquely_sized), output_sum),
+        None => println!("There are {} instances of {}. Outputs on a {} display aren't numbers", count, or_list(&uniquely_sized), display.name)
     }
-    println!("There are {} instances of 1, 4, 7 or 8. Output sum is {}", count, output_sum);
 }
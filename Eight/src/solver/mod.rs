@@ -0,0 +1,235 @@
+use crate::display::DisplayDefinition;
+
+// Works out which wire drives which segment from whatever patterns we've seen. A pattern is a
+// bitmask with bit n set when wire n is lit
+
+// mapping[wire] = segment
+pub type Mapping = Vec<u8>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Solution {
+    Solved { mapping: Mapping, decoded: String },
+    // More than one wiring fits. The output is still known if they all read it the same way
+    Ambiguous { mappings: Vec<Mapping>, decoded: Option<String> },
+    Unsolvable,
+}
+
+pub fn mapping_to_string(display: &DisplayDefinition, mapping: &[u8]) -> String {
+    mapping.iter().enumerate()
+        .map(|(wire, segment)| format!("{}->{}", display.wire_name(wire), display.wire_name(*segment as usize)))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+pub fn apply(mapping: &[u8], pattern: u32) -> u32 {
+    let mut segments = 0u32;
+    for (wire, segment) in mapping.iter().enumerate() {
+        if pattern & (1 << wire) != 0 {
+            segments |= 1 << segment;
+        }
+    }
+    segments
+}
+
+pub fn decode_symbol(display: &DisplayDefinition, mapping: &[u8], pattern: u32) -> Option<char> {
+    display.symbol_for(apply(mapping, pattern))
+}
+
+fn decode_output(display: &DisplayDefinition, mapping: &[u8], outputs: &[u32]) -> Option<String> {
+    outputs.iter().map(|o| decode_symbol(display, mapping, *o)).collect()
+}
+
+// Narrow down which segments each wire could drive. A wire lit in a pattern has to drive a segment of
+// one of the symbols that pattern could be, and a wire that's off has to drive a segment outside it
+fn propagate(display: &DisplayDefinition, patterns: &[u32]) -> Option<Vec<u32>> {
+    let all_segments = display.all_segments();
+    let mut domains = vec![all_segments; display.num_segments];
+    for pattern in patterns {
+        let mut lit = 0u32;
+        let mut unlit = 0u32;
+        for (_, symbol) in display.symbols.iter().filter(|(_, s)| s.count_ones() == pattern.count_ones()) {
+            lit |= symbol;
+            unlit |= !symbol & all_segments;
+        }
+        for (wire, domain) in domains.iter_mut().enumerate() {
+            *domain &= if pattern & (1 << wire) != 0 { lit } else { unlit };
+        }
+    }
+    // A wire with only one option takes that segment away from everyone else
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for wire in 0..domains.len() {
+            if domains[wire] == 0 {
+                return None;
+            }
+            if domains[wire].count_ones() == 1 {
+                let taken = domains[wire];
+                for (other, domain) in domains.iter_mut().enumerate() {
+                    if other != wire && *domain & taken != 0 {
+                        *domain &= !taken;
+                        changed = true;
+                    }
+                }
+            }
+        }
+    }
+    Some(domains)
+}
+
+struct Search<'a> {
+    display: &'a DisplayDefinition,
+    patterns: &'a [u32],
+    domains: Vec<u32>,
+    mapping: Mapping,
+    solutions: Vec<Mapping>,
+    // Stop collecting once we have this many, there can be millions on a 16 segment display
+    limit: usize,
+}
+
+impl Search<'_> {
+    // With the first `assigned` wires placed, every pattern still has to be able to become some
+    // symbol. The placed wires that are lit must be in it and the placed ones that are off mustn't
+    fn still_possible(&self, assigned: usize) -> bool {
+        let assigned_wires = (1u32 << assigned) - 1;
+        self.patterns.iter().all(|pattern| {
+            let on = apply(&self.mapping[..assigned], pattern & assigned_wires);
+            let off = apply(&self.mapping[..assigned], !pattern & assigned_wires);
+            self.display.symbols.iter().any(|(_, symbol)| {
+                symbol.count_ones() == pattern.count_ones() && symbol & on == on && symbol & off == 0
+            })
+        })
+    }
+
+    fn run(&mut self, wire: usize, used: u32) {
+        if self.solutions.len() >= self.limit {
+            return;
+        }
+        if wire == self.domains.len() {
+            self.solutions.push(self.mapping.clone());
+            return;
+        }
+        for segment in 0..self.domains.len() {
+            if self.domains[wire] & (1 << segment) == 0 || used & (1 << segment) != 0 {
+                continue;
+            }
+            self.mapping[wire] = segment as u8;
+            if self.still_possible(wire + 1) {
+                self.run(wire + 1, used | (1 << segment));
+            }
+        }
+    }
+}
+
+// Wirings that fit all of the observed patterns, up to `limit` of them
+pub fn find_mappings(display: &DisplayDefinition, patterns: &[u32], limit: usize) -> Vec<Mapping> {
+    let domains = match propagate(display, patterns) {
+        None => return vec![],
+        Some(domains) => domains
+    };
+    let mut search = Search {
+        display,
+        patterns,
+        mapping: vec![0; domains.len()],
+        domains,
+        solutions: Vec::new(),
+        limit
+    };
+    search.run(0, 0);
+    search.solutions
+}
+
+// Enough to show off an ambiguous line without spending forever listing every wiring
+const MAPPING_LIMIT: usize = 10_000;
+
+// Observations are every pattern we've seen, including the outputs
+pub fn solve(display: &DisplayDefinition, observations: &[u32], outputs: &[u32]) -> Solution {
+    let mappings = find_mappings(display, observations, MAPPING_LIMIT);
+    match mappings.len() {
+        0 => Solution::Unsolvable,
+        1 => match decode_output(display, &mappings[0], outputs) {
+            None => Solution::Unsolvable,
+            Some(decoded) => Solution::Solved { mapping: mappings[0].clone(), decoded }
+        },
+        _ => {
+            // If we hit the limit there could be wirings we never saw so we can't vouch for the output
+            let first = decode_output(display, &mappings[0], outputs);
+            let agree = mappings.len() < MAPPING_LIMIT && mappings.iter().all(|m| decode_output(display, m, outputs) == first);
+            Solution::Ambiguous { mappings, decoded: if agree { first } else { None } }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A seeded xorshift so the permutations are scrambled but the same every run
+    fn permutations(count: usize) -> Vec<Mapping> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        (0..count).map(|_| {
+            let mut mapping: Mapping = (0..7).collect();
+            for i in (1..mapping.len()).rev() {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                mapping.swap(i, (state % (i as u64 + 1)) as usize);
+            }
+            mapping
+        }).collect()
+    }
+
+    // The wires lit when `symbol` is shown through `mapping`
+    fn scramble(display: &DisplayDefinition, mapping: &[u8], symbol: char) -> u32 {
+        let segments = display.symbols.iter().find(|(s, _)| *s == symbol).unwrap().1;
+        (0..mapping.len()).filter(|wire| segments & (1 << mapping[*wire]) != 0).fold(0, |p, wire| p | (1 << wire))
+    }
+
+    #[test]
+    fn recovers_the_wiring_without_a_1_or_a_4() {
+        let display = DisplayDefinition::seven_segment();
+        for mapping in permutations(50) {
+            let observations: Vec<u32> = "02356789".chars().map(|s| scramble(&display, &mapping, s)).collect();
+            let outputs: Vec<u32> = "9072".chars().map(|s| scramble(&display, &mapping, s)).collect();
+            assert_eq!(apply(&mapping, outputs[0]), display.symbols[9].1);
+            assert_eq!(
+                solve(&display, &observations, &outputs),
+                Solution::Solved { mapping: mapping.clone(), decoded: "9072".to_string() },
+                "{}", mapping_to_string(&display, &mapping)
+            );
+        }
+    }
+
+    #[test]
+    fn all_ten_digits_leave_only_the_scrambling_wiring() {
+        let display = DisplayDefinition::seven_segment();
+        for mapping in permutations(20) {
+            let observations: Vec<u32> = "0123456789".chars().map(|s| scramble(&display, &mapping, s)).collect();
+            assert_eq!(find_mappings(&display, &observations, MAPPING_LIMIT), vec![mapping]);
+        }
+    }
+
+    #[test]
+    fn too_few_observations_are_ambiguous() {
+        let display = DisplayDefinition::seven_segment();
+        let mapping = &permutations(1)[0];
+        let one = scramble(&display, mapping, '1');
+        let seven = scramble(&display, mapping, '7');
+        match solve(&display, &[one, seven], &[one, seven]) {
+            Solution::Ambiguous { mappings, decoded } => {
+                assert!(mappings.contains(mapping));
+                // Only the two wires of 1 and the top bar are pinned down, and they all read 1 and 7 alike
+                assert_eq!(mappings.len(), 2 * 24);
+                assert_eq!(decoded, Some("17".to_string()));
+            }
+            other => panic!("Expected an ambiguous wiring, got {:?}", other)
+        }
+        // An 8 lights every wire so it says nothing, and what a 2 would look like isn't settled
+        let eight = scramble(&display, mapping, '8');
+        let two = scramble(&display, mapping, '2');
+        match solve(&display, &[eight, one, two], &[two]) {
+            Solution::Ambiguous { decoded, .. } => assert_eq!(decoded, None),
+            other => panic!("Expected an ambiguous wiring, got {:?}", other)
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::{HashMap, HashSet};
+use crate::line::Line;
+
+// Counts the lattice points covered by two or more lines without rasterizing them. Only the points
+// that lie exactly on a segment count, which is what the grid engine produces for horizontal,
+// vertical and 45 degree lines. Other slopes get extra cells from the rasterizer so the two engines
+// will disagree on those.
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+fn cross(a: (i64, i64), b: (i64, i64)) -> i64 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+fn dot(a: (i64, i64), b: (i64, i64)) -> i64 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+struct Segment {
+    start: (i64, i64),
+    // smallest lattice step along the segment
+    direction: (i64, i64),
+    // number of steps from start to end
+    steps: i64,
+    min_x: i64,
+    max_x: i64,
+    min_y: i64,
+    max_y: i64,
+}
+
+impl Segment {
+    fn from_line(line: &Line) -> Segment {
+        let start = (line.start_x, line.start_y);
+        let end = (line.end_x, line.end_y);
+        let delta = (end.0 - start.0, end.1 - start.1);
+        let steps = gcd(delta.0, delta.1);
+        let direction = if steps == 0 { (1, 0) } else { (delta.0 / steps, delta.1 / steps) };
+        Segment {
+            start,
+            direction,
+            steps,
+            min_x: start.0.min(end.0),
+            max_x: start.0.max(end.0),
+            min_y: start.1.min(end.1),
+            max_y: start.1.max(end.1),
+        }
+    }
+
+    // Direction with a consistent sign so both orientations of a line share a key
+    fn canonical_direction(&self) -> (i64, i64) {
+        let (dx, dy) = self.direction;
+        if dx < 0 || (dx == 0 && dy < 0) { (-dx, -dy) } else { (dx, dy) }
+    }
+
+    fn point_at(&self, step: i64) -> (i64, i64) {
+        (self.start.0 + step * self.direction.0, self.start.1 + step * self.direction.1)
+    }
+}
+
+// Overlapping runs on a single infinite line. Positions along the line are measured as the dot
+// product with the canonical direction so every lattice point on the line is a multiple of
+// |direction|² apart
+#[derive(Default)]
+struct CollinearRuns {
+    intervals: Vec<(i64, i64)>,
+}
+
+impl CollinearRuns {
+    fn merge(&mut self) {
+        self.intervals.sort_unstable();
+        let mut merged: Vec<(i64, i64)> = Vec::with_capacity(self.intervals.len());
+        for &(lo, hi) in self.intervals.iter() {
+            match merged.last_mut() {
+                Some(last) if lo <= last.1 => last.1 = last.1.max(hi),
+                _ => merged.push((lo, hi))
+            }
+        }
+        self.intervals = merged;
+    }
+
+    fn count(&self, spacing: i64) -> usize {
+        self.intervals.iter().map(|(lo, hi)| ((hi - lo) / spacing + 1) as usize).sum()
+    }
+
+    fn contains(&self, position: i64) -> bool {
+        let index = self.intervals.partition_point(|(_, hi)| *hi < position);
+        match self.intervals.get(index) {
+            None => false,
+            Some((lo, _)) => *lo <= position
+        }
+    }
+}
+
+#[derive(Default)]
+struct Overlaps {
+    // direction -> offset of the line from the origin (cross product) -> runs
+    collinear: HashMap<(i64, i64), HashMap<i64, CollinearRuns>>,
+    crossings: HashSet<(i64, i64)>,
+}
+
+impl Overlaps {
+    fn add_pair(&mut self, a: &Segment, b: &Segment) {
+        let denominator = cross(a.direction, b.direction);
+        let offset = (b.start.0 - a.start.0, b.start.1 - a.start.1);
+        if denominator != 0 {
+            // The lines cross at one point. It has to land on a lattice step of both segments
+            let t_numerator = cross(offset, b.direction);
+            let s_numerator = cross(offset, a.direction);
+            if t_numerator % denominator != 0 || s_numerator % denominator != 0 {
+                return;
+            }
+            let t = t_numerator / denominator;
+            let s = s_numerator / denominator;
+            if (0..=a.steps).contains(&t) && (0..=b.steps).contains(&s) {
+                self.crossings.insert(a.point_at(t));
+            }
+            return;
+        }
+        if cross(offset, a.direction) != 0 {
+            return; // parallel but not on the same line
+        }
+        let direction = a.canonical_direction();
+        let a_end = a.point_at(a.steps);
+        let b_end = b.point_at(b.steps);
+        let (a_lo, a_hi) = ordered(dot(a.start, direction), dot(a_end, direction));
+        let (b_lo, b_hi) = ordered(dot(b.start, direction), dot(b_end, direction));
+        let lo = a_lo.max(b_lo);
+        let hi = a_hi.min(b_hi);
+        if lo > hi {
+            return;
+        }
+        self.collinear.entry(direction).or_default()
+            .entry(cross(a.start, direction)).or_default()
+            .intervals.push((lo, hi));
+    }
+
+    fn count(mut self) -> usize {
+        let mut total = 0;
+        for (direction, lines) in self.collinear.iter_mut() {
+            let spacing = dot(*direction, *direction);
+            for runs in lines.values_mut() {
+                runs.merge();
+                total += runs.count(spacing);
+            }
+        }
+        // Crossing points that already sit inside a collinear run have been counted. If they sit
+        // inside runs on more than one line they've been counted once per line
+        for point in self.crossings.iter() {
+            let containing_runs = self.collinear.iter().filter(|(direction, lines)| {
+                match lines.get(&cross(*point, **direction)) {
+                    None => false,
+                    Some(runs) => runs.contains(dot(*point, **direction))
+                }
+            }).count();
+            if containing_runs == 0 {
+                total += 1;
+            } else {
+                total -= containing_runs - 1;
+            }
+        }
+        total
+    }
+}
+
+fn ordered(a: i64, b: i64) -> (i64, i64) {
+    if a <= b { (a, b) } else { (b, a) }
+}
+
+pub fn count_overlaps(lines: &[Line]) -> usize {
+    let mut segments: Vec<Segment> = lines.iter().map(Segment::from_line).collect();
+    // Going left to right we only compare against segments whose x range is still open. That skips pairs
+    // that are far apart, but it's still quadratic when lots of segments overlap in x
+    segments.sort_unstable_by_key(|segment| segment.min_x);
+    let mut overlaps = Overlaps::default();
+    let mut active: Vec<&Segment> = Vec::new();
+    for segment in segments.iter() {
+        active.retain(|other| other.max_x >= segment.min_x);
+        for other in active.iter() {
+            if other.max_y < segment.min_y || other.min_y > segment.max_y {
+                continue;
+            }
+            overlaps.add_pair(other, segment);
+        }
+        active.push(segment);
+    }
+    overlaps.count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::{SparseGrid, VentGrid};
+    use crate::line::Rasterization;
+
+    fn count_with_grid(lines: &[Line]) -> usize {
+        let mut grid = SparseGrid::new();
+        let mut overlaps = 0;
+        for line in lines.iter() {
+            for (x, y) in line.iter_with(Rasterization::Bresenham) {
+                if grid.increment(x, y) == 2 {
+                    overlaps += 1;
+                }
+            }
+        }
+        overlaps
+    }
+
+    fn sample(diagonal: bool) -> Vec<Line> {
+        let sample = [
+            (0, 9, 5, 9), (8, 0, 0, 8), (9, 4, 3, 4), (2, 2, 2, 1), (7, 0, 7, 4),
+            (6, 4, 2, 0), (0, 9, 2, 9), (3, 4, 1, 4), (0, 0, 8, 8), (5, 5, 8, 2),
+        ];
+        sample.iter()
+            .map(|(x1, y1, x2, y2)| Line::new(*x1, *y1, *x2, *y2))
+            .filter(|line| diagonal || line.is_horizontal() || line.is_vertical())
+            .collect()
+    }
+
+    #[test]
+    fn matches_grid_on_sample() {
+        assert_eq!(count_overlaps(&sample(false)), 5);
+        assert_eq!(count_with_grid(&sample(false)), 5);
+        assert_eq!(count_overlaps(&sample(true)), 12);
+        assert_eq!(count_with_grid(&sample(true)), 12);
+    }
+
+    // Small xorshift so the test doesn't need a crate for random numbers
+    struct Random(u64);
+
+    impl Random {
+        fn below(&mut self, n: i64) -> i64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as i64
+        }
+    }
+
+    #[test]
+    fn matches_grid_on_random_lines() {
+        let mut random = Random(0x2545_f491_4f6c_dd1d);
+        for _ in 0..200 {
+            let lines: Vec<Line> = (0..30).map(|_| {
+                let (x, y) = (random.below(40) - 10, random.below(40) - 10);
+                let length = random.below(20);
+                let (dx, dy) = match random.below(3) {
+                    0 => (1, 0),
+                    1 => (0, 1),
+                    _ => (1, if random.below(2) == 0 { 1 } else { -1 })
+                };
+                let sign = if random.below(2) == 0 { 1 } else { -1 };
+                Line::new(x, y, x + sign * dx * length, y + sign * dy * length)
+            }).collect();
+            assert_eq!(count_overlaps(&lines), count_with_grid(&lines));
+        }
+    }
+}
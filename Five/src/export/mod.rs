@@ -0,0 +1,98 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use crate::grid::{Bounds, VentGrid, DENSE_CELL_LIMIT};
+
+// Stops at the same size we'd be willing to allocate a dense grid for. Anything bigger needs cropping
+fn check_region(region: &Bounds) -> std::io::Result<()> {
+    if region.area() > DENSE_CELL_LIMIT {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "The region is too big to export, crop it first"));
+    }
+    Ok(())
+}
+
+// black -> blue -> red -> yellow -> white as the count goes from 0 to max_count
+fn heat_colour(count: u32, max_count: u32) -> (u8, u8, u8) {
+    if count == 0 || max_count == 0 {
+        return (0, 0, 0);
+    }
+    const STOPS: [(f64, f64, f64); 4] = [(0.0, 0.0, 255.0), (255.0, 0.0, 0.0), (255.0, 255.0, 0.0), (255.0, 255.0, 255.0)];
+    if max_count == 1 {
+        return (0, 0, 255);
+    }
+    let position = (count - 1) as f64 / (max_count - 1) as f64 * (STOPS.len() - 1) as f64;
+    let lower = (position.floor() as usize).min(STOPS.len() - 2);
+    let fraction = position - lower as f64;
+    let (r1, g1, b1) = STOPS[lower];
+    let (r2, g2, b2) = STOPS[lower + 1];
+    (
+        (r1 + (r2 - r1) * fraction) as u8,
+        (g1 + (g2 - g1) * fraction) as u8,
+        (b1 + (b2 - b1) * fraction) as u8,
+    )
+}
+
+fn grey(count: u32, max_count: u32) -> u8 {
+    if max_count == 0 {
+        return 0;
+    }
+    (count as u64 * 255 / max_count as u64) as u8
+}
+
+// Binary greyscale image scaled so the busiest cell is white
+pub fn write_pgm(grid: &impl VentGrid, region: &Bounds, filename: &str) -> std::io::Result<()> {
+    check_region(region)?;
+    let max_count = grid.max_count();
+    let mut writer = BufWriter::new(File::create(filename)?);
+    write!(writer, "P5\n{} {}\n255\n", region.width(), region.height())?;
+    for y in region.min_y..=region.max_y {
+        let row: Vec<u8> = (region.min_x..=region.max_x).map(|x| grey(grid.get(x, y), max_count)).collect();
+        writer.write_all(&row)?;
+    }
+    writer.flush()
+}
+
+// Binary colour image using the same palette as the terminal heatmap
+pub fn write_ppm(grid: &impl VentGrid, region: &Bounds, filename: &str) -> std::io::Result<()> {
+    check_region(region)?;
+    let max_count = grid.max_count();
+    let mut writer = BufWriter::new(File::create(filename)?);
+    write!(writer, "P6\n{} {}\n255\n", region.width(), region.height())?;
+    for y in region.min_y..=region.max_y {
+        let mut row = Vec::with_capacity(region.width() as usize * 3);
+        for x in region.min_x..=region.max_x {
+            let (r, g, b) = heat_colour(grid.get(x, y), max_count);
+            row.extend_from_slice(&[r, g, b]);
+        }
+        writer.write_all(&row)?;
+    }
+    writer.flush()
+}
+
+fn ansi_block(colour: (u8, u8, u8)) -> String {
+    format!("\x1b[48;2;{};{};{}m  \x1b[0m", colour.0, colour.1, colour.2)
+}
+
+// Each cell is two spaces wide so it comes out roughly square. The legend goes underneath
+pub fn ansi_heatmap(grid: &impl VentGrid, region: &Bounds) -> std::io::Result<String> {
+    check_region(region)?;
+    let max_count = grid.max_count();
+    let mut heatmap = String::new();
+    for y in region.min_y..=region.max_y {
+        for x in region.min_x..=region.max_x {
+            heatmap.push_str(ansi_block(heat_colour(grid.get(x, y), max_count)).as_str());
+        }
+        heatmap.push('\n');
+    }
+    heatmap.push_str(format!("({},{}) to ({},{})\n", region.min_x, region.min_y, region.max_x, region.max_y).as_str());
+    // Show every count when there aren't many, otherwise a handful of evenly spaced ones
+    let legend_counts: Vec<u32> = if max_count <= 10 {
+        (0..=max_count).collect()
+    } else {
+        (0..=8).map(|i| (i as u64 * max_count as u64 / 8) as u32).collect()
+    };
+    for count in legend_counts {
+        heatmap.push_str(format!("{} {} ", ansi_block(heat_colour(count, max_count)), count).as_str());
+    }
+    heatmap.push('\n');
+    Ok(heatmap)
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+// Anything bigger than this gets a sparse grid. 16M u32 cells is 64MB which is about as much as we
+// want to allocate up front
+pub const DENSE_CELL_LIMIT: u64 = 1 << 24;
+
+// Inclusive bounding box of every point in the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub min_x: i64,
+    pub min_y: i64,
+    pub max_x: i64,
+    pub max_y: i64,
+}
+
+impl Bounds {
+    pub fn from_point(x: i64, y: i64) -> Bounds {
+        Bounds {
+            min_x: x,
+            min_y: y,
+            max_x: x,
+            max_y: y
+        }
+    }
+
+    pub fn include(&mut self, x: i64, y: i64) {
+        self.min_x = self.min_x.min(x);
+        self.min_y = self.min_y.min(y);
+        self.max_x = self.max_x.max(x);
+        self.max_y = self.max_y.max(y);
+    }
+
+    pub fn width(&self) -> u64 {
+        (self.max_x - self.min_x) as u64 + 1
+    }
+
+    pub fn height(&self) -> u64 {
+        (self.max_y - self.min_y) as u64 + 1
+    }
+
+    pub fn area(&self) -> u64 {
+        self.width().saturating_mul(self.height())
+    }
+}
+
+pub trait VentGrid {
+    // Bumps the count at the point and returns the new count. Counts saturate rather than wrap
+    fn increment(&mut self, x: i64, y: i64) -> u32;
+    fn get(&self, x: i64, y: i64) -> u32;
+    fn max_count(&self) -> u32;
+}
+
+// Cells are stored relative to the top left of the bounding box so negative coordinates are fine
+pub struct DenseGrid {
+    data: Vec<u32>,
+    bounds: Bounds,
+}
+
+impl DenseGrid {
+    pub fn new(bounds: Bounds) -> DenseGrid {
+        DenseGrid {
+            data: vec![0; (bounds.width() * bounds.height()) as usize],
+            bounds,
+        }
+    }
+
+    fn index(&self, x: i64, y: i64) -> Option<usize> {
+        if x < self.bounds.min_x || x > self.bounds.max_x || y < self.bounds.min_y || y > self.bounds.max_y {
+            return None;
+        }
+        let column = (x - self.bounds.min_x) as u64;
+        let row = (y - self.bounds.min_y) as u64;
+        Some((row * self.bounds.width() + column) as usize)
+    }
+}
+
+impl VentGrid for DenseGrid {
+    fn increment(&mut self, x: i64, y: i64) -> u32 {
+        let index = self.index(x, y).expect("Point is outside of the grid");
+        let cell = &mut self.data[index];
+        *cell = cell.saturating_add(1);
+        *cell
+    }
+
+    fn get(&self, x: i64, y: i64) -> u32 {
+        match self.index(x, y) {
+            None => 0,
+            Some(index) => self.data[index]
+        }
+    }
+
+    fn max_count(&self) -> u32 {
+        self.data.iter().copied().max().unwrap_or(0)
+    }
+}
+
+#[derive(Default)]
+pub struct SparseGrid {
+    data: HashMap<(i64, i64), u32>,
+}
+
+impl SparseGrid {
+    pub fn new() -> SparseGrid {
+        SparseGrid {
+            data: HashMap::new()
+        }
+    }
+}
+
+impl VentGrid for SparseGrid {
+    fn increment(&mut self, x: i64, y: i64) -> u32 {
+        let cell = self.data.entry((x, y)).or_insert(0);
+        *cell = cell.saturating_add(1);
+        *cell
+    }
+
+    fn get(&self, x: i64, y: i64) -> u32 {
+        self.data.get(&(x, y)).copied().unwrap_or(0)
+    }
+
+    fn max_count(&self) -> u32 {
+        self.data.values().copied().max().unwrap_or(0)
+    }
+}
+
+pub enum Grid {
+    Dense(DenseGrid),
+    Sparse(SparseGrid),
+}
+
+impl Grid {
+    // Picks a backend based on how spread out the coordinates are
+    pub fn for_bounds(bounds: Option<Bounds>) -> Grid {
+        match bounds {
+            Some(bounds) if bounds.area() <= DENSE_CELL_LIMIT => {
+                Grid::Dense(DenseGrid::new(bounds))
+            }
+            _ => Grid::Sparse(SparseGrid::new())
+        }
+    }
+}
+
+impl VentGrid for Grid {
+    fn increment(&mut self, x: i64, y: i64) -> u32 {
+        match self {
+            Grid::Dense(grid) => grid.increment(x, y),
+            Grid::Sparse(grid) => grid.increment(x, y)
+        }
+    }
+
+    fn get(&self, x: i64, y: i64) -> u32 {
+        match self {
+            Grid::Dense(grid) => grid.get(x, y),
+            Grid::Sparse(grid) => grid.get(x, y)
+        }
+    }
+
+    fn max_count(&self) -> u32 {
+        match self {
+            Grid::Dense(grid) => grid.max_count(),
+            Grid::Sparse(grid) => grid.max_count()
+        }
+    }
+}
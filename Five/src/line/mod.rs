@@ -0,0 +1,144 @@
+// Coordinates have to be within this of 0. The rasterizers multiply a line's length along one axis by
+// its length along the other and the analytic engine takes cross products of them, so this keeps
+// everything well inside an i64
+pub const MAX_COORDINATE: i64 = 1 << 29;
+
+pub struct Line {
+    pub start_x: i64,
+    pub start_y: i64,
+    pub end_x: i64,
+    pub end_y: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rasterization {
+    // Classic Bresenham. One cell per step along the major axis
+    Bresenham,
+    // Every cell whose interior the line passes through. Lines that pass exactly through a corner
+    // step diagonally so 45 degree lines come out the same as Bresenham
+    Supercover,
+}
+
+impl Line {
+    pub fn new(start_x: i64, start_y: i64, end_x: i64, end_y: i64) -> Line {
+        Line {
+            start_x,
+            start_y,
+            end_x,
+            end_y
+        }
+    }
+
+    pub fn is_horizontal(&self) -> bool {
+        self.start_y == self.end_y
+    }
+
+    pub fn is_vertical(&self) -> bool {
+        self.start_x == self.end_x
+    }
+
+    // True when every cell the rasterizer produces sits exactly on the line
+    pub fn is_lattice_exact(&self) -> bool {
+        let dx = (self.end_x - self.start_x).abs();
+        let dy = (self.end_y - self.start_y).abs();
+        dx == 0 || dy == 0 || dx == dy
+    }
+
+    pub fn iter_with(&self, rasterization: Rasterization) -> LineIterator {
+        let dx = (self.end_x - self.start_x).abs();
+        let dy = (self.end_y - self.start_y).abs();
+        LineIterator {
+            rasterization,
+            x: self.start_x,
+            y: self.start_y,
+            step_x: if self.start_x < self.end_x { 1 } else { -1 },
+            step_y: if self.start_y < self.end_y { 1 } else { -1 },
+            dx,
+            dy,
+            error: dx - dy,
+            taken_x: 0,
+            taken_y: 0,
+            done: false
+        }
+    }
+}
+
+pub struct LineIterator {
+    rasterization: Rasterization,
+    x: i64,
+    y: i64,
+    step_x: i64,
+    step_y: i64,
+    dx: i64,
+    dy: i64,
+    // Bresenham error term
+    error: i64,
+    // Supercover progress along each axis
+    taken_x: i64,
+    taken_y: i64,
+    done: bool,
+}
+
+impl LineIterator {
+    fn advance_bresenham(&mut self) {
+        let doubled_error = 2 * self.error;
+        if doubled_error >= -self.dy {
+            self.error -= self.dy;
+            self.x += self.step_x;
+        }
+        if doubled_error <= self.dx {
+            self.error += self.dx;
+            self.y += self.step_y;
+        }
+    }
+
+    fn advance_supercover(&mut self) {
+        // Compare where the line crosses the next vertical and horizontal cell boundaries. Cell
+        // centres sit on the integer coordinates so the boundaries are at the half steps
+        let to_vertical_boundary = (1 + 2 * self.taken_x) * self.dy;
+        let to_horizontal_boundary = (1 + 2 * self.taken_y) * self.dx;
+        if to_vertical_boundary == to_horizontal_boundary {
+            self.x += self.step_x;
+            self.taken_x += 1;
+            self.y += self.step_y;
+            self.taken_y += 1;
+        } else if to_vertical_boundary < to_horizontal_boundary {
+            self.x += self.step_x;
+            self.taken_x += 1;
+        } else {
+            self.y += self.step_y;
+            self.taken_y += 1;
+        }
+    }
+}
+
+impl Iterator for LineIterator {
+    type Item = (i64, i64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None
+        }
+        let current = (self.x, self.y);
+        match self.rasterization {
+            Rasterization::Bresenham => {
+                if self.taken_x == self.dx && self.taken_y == self.dy {
+                    self.done = true;
+                } else {
+                    let (old_x, old_y) = (self.x, self.y);
+                    self.advance_bresenham();
+                    self.taken_x += (self.x - old_x).abs();
+                    self.taken_y += (self.y - old_y).abs();
+                }
+            }
+            Rasterization::Supercover => {
+                if self.taken_x == self.dx && self.taken_y == self.dy {
+                    self.done = true;
+                } else {
+                    self.advance_supercover();
+                }
+            }
+        }
+        Some(current)
+    }
+}
@@ -1,214 +1,205 @@
+use std::env;
+use std::fmt::{Display, Formatter};
 use std::fs;
-use std::panic;
+use crate::grid::{Bounds, Grid, VentGrid};
+use crate::line::{Line, Rasterization, MAX_COORDINATE};
 
-struct Line {
-    start_x: u32,
-    start_y: u32,
-    end_x: u32,
-    end_y: u32,
+mod analytic;
+mod export;
+mod grid;
+mod line;
+
+#[derive(Debug)]
+struct ParseError {
+    line_number: usize,
+    line: String,
+    reason: &'static str,
 }
 
-impl Line {
-    fn new(start_x:u32, start_y: u32, end_x: u32, end_y: u32) -> Line {
-        Line {
-            start_x,
-            start_y,
-            end_x,
-            end_y
-        }
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {} in \"{}\"", self.line_number, self.reason, self.line)
     }
-    fn iter(&self) -> LineIterator {
-        let vertical = self.start_x == self.end_x;
-        let horizontal = self.start_y == self.end_y;
-        let num_steps;
-        if vertical {
-            num_steps = (self.end_y as i32 - self.start_y as i32).unsigned_abs();
-        } else {
-            num_steps = (self.end_x as i32 - self.start_x as i32).unsigned_abs();
-        }
-        let diagonal_direction = DiagonalDirection::from_points(self.start_x, self.start_y, self.end_x, self.end_y);
-        LineIterator {
-            line: self,
-            step: 0,
-            num_steps,
-            vertical,
-            horizontal,
-            diagonal_direction
-        }
+}
+
+fn parse_point(point: &str) -> Result<(i64, i64), &'static str> {
+    let (x, y) = point.trim().split_once(',').ok_or("a coordinate is missing a comma")?;
+    let x = x.trim().parse::<i64>().map_err(|_| "couldn't parse an x coordinate")?;
+    let y = y.trim().parse::<i64>().map_err(|_| "couldn't parse a y coordinate")?;
+    if x.unsigned_abs() > MAX_COORDINATE as u64 || y.unsigned_abs() > MAX_COORDINATE as u64 {
+        return Err("a coordinate is further from 0 than 536870912");
     }
+    Ok((x, y))
 }
-enum DiagonalDirection {
-    NE,
-    SE,
-    SW,
-    NW
+
+fn parse_line(line: &str) -> Result<Line, &'static str> {
+    let (start, end) = line.split_once("->").ok_or("a line is missing its \"->\"")?;
+    let (start_x, start_y) = parse_point(start)?;
+    let (end_x, end_y) = parse_point(end)?;
+    Ok(Line::new(start_x, start_y, end_x, end_y))
 }
 
-impl DiagonalDirection {
-    fn from_points(x1: u32, y1: u32, x2: u32, y2:u32) -> Option<DiagonalDirection> {
-        if x1 == x2 || y1 == y2 {
-            return None
-        }
-        return if x1 < x2 {
-            //east
-            if y1 < y2 {
-                //north
-                Some(DiagonalDirection::NE)
-            } else {
-                //south
-                Some(DiagonalDirection::SE)
-            }
-        } else {
-            //west
-            if y1 < y2 {
-                //north
-                Some(DiagonalDirection::NW)
-            } else {
-                //south
-                Some(DiagonalDirection::SW)
+fn read_input(filename: &str, diagonal: bool) -> Result<(Vec<Line>, Option<Bounds>), ParseError> {
+    let contents = fs::read_to_string(filename).expect("Something went wrong reading the file");
+    let mut lines = Vec::new();
+    let mut bounds: Option<Bounds> = None;
+    for (index, text) in contents.lines().enumerate() {
+        if text.trim().is_empty() {
+            continue;
+        }
+        let line = parse_line(text).map_err(|reason| ParseError {
+            line_number: index + 1,
+            line: text.to_string(),
+            reason
+        })?;
+        if !(diagonal || line.is_horizontal() || line.is_vertical()) {
+            continue;
+        }
+        for (x, y) in [(line.start_x, line.start_y), (line.end_x, line.end_y)] {
+            match bounds.as_mut() {
+                None => bounds = Some(Bounds::from_point(x, y)),
+                Some(bounds) => bounds.include(x, y)
             }
         }
+        lines.push(line);
     }
+    Ok((lines, bounds))
 }
 
-struct LineIterator<'a> {
-    line : &'a Line,
-    step: u32,
-    num_steps: u32,
-    vertical: bool,
-    horizontal: bool,
-    diagonal_direction: Option<DiagonalDirection>
-}
-
-impl Iterator for LineIterator<'_> {
-    type Item = (u32, u32);
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.step > self.num_steps {
-            return None
-        }
-        let current_step = self.step;
-        self.step += 1;
-        return if self.vertical {
-            return if self.line.start_y < self.line.end_y {
-                Some((self.line.start_x, self.line.start_y + current_step))
-            } else {
-                Some((self.line.start_x, self.line.start_y - current_step))
-            }
-        } else if self.horizontal {
-            return if self.line.start_x < self.line.end_x {
-                Some((self.line.start_x + current_step, self.line.start_y))
-            } else {
-                Some((self.line.start_x - current_step, self.line.start_y))
-            }
-        } else {
-            // diagonal
-            match &self.diagonal_direction {
-                None => panic! ("Line is not horizontal, vertical or diagonal..."),
-                Some(dd) => {
-                    return match dd {
-                        DiagonalDirection::NE => {
-                            Some((self.line.start_x + current_step, self.line.start_y + current_step))
-                        }
-                        DiagonalDirection::SE => {
-                            Some((self.line.start_x + current_step, self.line.start_y - current_step))
-                        }
-                        DiagonalDirection::SW => {
-                            Some((self.line.start_x - current_step, self.line.start_y - current_step))
-                        }
-                        DiagonalDirection::NW => {
-                            Some((self.line.start_x - current_step, self.line.start_y + current_step))
-                        }
-                    }
-                }
+fn fill_grid(lines: &[Line], bounds: Option<Bounds>, rasterization: Rasterization) -> (Grid, usize) {
+    let mut grid = Grid::for_bounds(bounds);
+    let mut overlap_counter = 0;
+    for line in lines.iter() {
+        for point in line.iter_with(rasterization) {
+            if grid.increment(point.0, point.1) == 2 {
+                overlap_counter += 1;
             }
         }
     }
+    (grid, overlap_counter)
 }
 
-fn read_input(filename: &str, diagonal: bool) -> (Vec<Line>, u32, u32) {
-    let contents = fs::read_to_string(filename).expect("Something went wrong reading the file");
-    let mut lines = Vec::new();
-    let mut max_x = 0;
-    let mut max_y = 0;
-    for line in contents.lines() {
-        let parts: Vec<&str> = line.split(" -> ").collect();
-        if parts.len() != 2 {
-            panic!("A line has more than two elements");
-        }
-        let start_point_parts: Vec<&str> = parts[0].split(",").collect();
-        if start_point_parts.len() != 2 {
-            panic!("A coordinate has too many elements");
-        }
-        let start_x = start_point_parts[0].parse::<u32>().expect("Couldn't parse input");
-        if start_x > max_x {
-            max_x = start_x;
-        }
-        let start_y = start_point_parts[1].parse::<u32>().expect("Couldn't parse input");
-        if start_y > max_y {
-            max_y = start_y;
-        }
+enum Engine {
+    Grid(Rasterization),
+    Analytic,
+    // Runs both and makes sure they agree
+    Check,
+}
 
-        let end_point_parts: Vec<&str> = parts[1].split(",").collect();
-        if end_point_parts.len() != 2 {
-            panic!("A coordinate has too many elements");
-        }
-        let end_x = end_point_parts[0].parse::<u32>().expect("Couldn't parse input");
-        if end_x > max_x {
-            max_x = end_x;
-        }
-        let end_y = end_point_parts[1].parse::<u32>().expect("Couldn't parse input");
-        if end_y > max_y {
-            max_y = end_y;
-        }
+#[derive(Default)]
+struct ExportOptions {
+    // Writes <prefix>_<part>.pgm and <prefix>_<part>.ppm
+    image_prefix: Option<String>,
+    ansi: bool,
+    crop: Option<Bounds>,
+}
 
-        if diagonal || end_y == start_y || end_x == start_x {
-            lines.push(Line{
-                start_x,
-                start_y,
-                end_x,
-                end_y
-            });
-        }
+impl ExportOptions {
+    fn enabled(&self) -> bool {
+        self.image_prefix.is_some() || self.ansi
     }
-    (lines, max_x, max_y)
 }
 
-fn do_test(lines: Vec<Line>, mut max_x: u32, mut max_y: u32) {
-    max_x += 1;
-    max_y += 1;
-    let mut board = Vec::with_capacity(max_x as usize);
-    for i in 0..max_x {
-        let mut column: Vec<u8> = Vec::new();
-        column.resize(max_y as usize, 0);
-        board.push(column);
+fn export(grid: &Grid, bounds: Option<Bounds>, options: &ExportOptions, part: usize) -> std::io::Result<()> {
+    let region = match options.crop.or(bounds) {
+        None => return Ok(()), // nothing to draw
+        Some(region) => region
+    };
+    if let Some(prefix) = &options.image_prefix {
+        export::write_pgm(grid, &region, format!("{}_{}.pgm", prefix, part).as_str())?;
+        export::write_ppm(grid, &region, format!("{}_{}.ppm", prefix, part).as_str())?;
     }
-    let mut overlap_counter = 0;
-    for (i, line) in lines.iter().enumerate() {
-        // println!("start line {}", i);
-        for point in line.iter() {
-            // println!("point is {},{}", point.0, point.1);
-            board[point.0 as usize][point.1 as usize] += 1;
-            if board[point.0 as usize][point.1 as usize] == 2 {
-                overlap_counter += 1;
+    if options.ansi {
+        print!("{}", export::ansi_heatmap(grid, &region)?);
+    }
+    Ok(())
+}
+
+fn do_test(lines: Vec<Line>, bounds: Option<Bounds>, engine: &Engine, options: &ExportOptions, part: usize) {
+    // The analytic engine doesn't need a grid unless we're drawing one. Everything else fills it once
+    // and uses it for both
+    let filled = match engine {
+        Engine::Analytic if !options.enabled() => None,
+        Engine::Grid(rasterization) => Some(fill_grid(&lines, bounds, *rasterization)),
+        _ => Some(fill_grid(&lines, bounds, Rasterization::Bresenham))
+    };
+    if let Some((grid, _)) = &filled {
+        if options.enabled() {
+            if let Err(e) = export(grid, bounds, options, part) {
+                println!("Couldn't export the heatmap: {}", e);
             }
         }
     }
-    let mut debug_str = String::new();
-    for x in 0..max_x as usize {
-        for y in 0..max_y as usize {
-            debug_str += board[y][x].to_string().as_str();
-            debug_str += " ";
+    let from_grid = filled.map(|(_, overlaps)| overlaps).unwrap_or(0);
+    let overlap_counter = match engine {
+        Engine::Grid(_) => from_grid,
+        Engine::Analytic => analytic::count_overlaps(&lines),
+        Engine::Check => {
+            let from_analytic = analytic::count_overlaps(&lines);
+            if lines.iter().all(|line| line.is_lattice_exact()) {
+                if from_grid != from_analytic {
+                    println!("The grid and analytic engines disagree (grid {}, analytic {})", from_grid, from_analytic);
+                }
+            } else {
+                println!("Some lines aren't horizontal, vertical or 45 degrees so the engines are expected to differ (grid {}, analytic {})", from_grid, from_analytic);
+            }
+            from_grid
         }
-        debug_str += "\n"
-    }
-    // println!("{}\nThere were {} overlaps", debug_str, overlap_counter)
+    };
     println!("There were {} overlaps", overlap_counter);
 }
 
+fn parse_crop(crop: &str) -> Result<Bounds, String> {
+    let values: Vec<i64> = crop.split(',')
+        .map(|v| v.trim().parse::<i64>().map_err(|_| format!("\"{}\" isn't a number", v.trim())))
+        .collect::<Result<_, _>>()?;
+    if values.len() != 4 {
+        return Err(format!("it needs four numbers x1,y1,x2,y2 but has {}", values.len()));
+    }
+    let mut bounds = Bounds::from_point(values[0], values[1]);
+    bounds.include(values[2], values[3]);
+    Ok(bounds)
+}
+
 fn main() {
-    let (lines , mut max_x, mut max_y) = read_input("input", false);
-    do_test(lines, max_x, max_y);
-    let (lines , mut max_x, mut max_y) = read_input("input", true);
-    do_test(lines, max_x, max_y);
+    // Five [input] [bresenham|supercover|analytic|check] [--export prefix] [--ansi] [--crop x1,y1,x2,y2]
+    let args: Vec<String> = env::args().collect();
+    let filename = args.get(1).map(|f| f.as_str()).unwrap_or("input");
+    let engine = match args.get(2).map(|r| r.as_str()) {
+        Some("supercover") => Engine::Grid(Rasterization::Supercover),
+        Some("analytic") => Engine::Analytic,
+        Some("check") => Engine::Check,
+        _ => Engine::Grid(Rasterization::Bresenham)
+    };
+    let mut options = ExportOptions::default();
+    let mut flags = args.iter().skip(3);
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "--export" => options.image_prefix = flags.next().cloned(),
+            "--ansi" => options.ansi = true,
+            "--crop" => {
+                match flags.next().map(|c| parse_crop(c)) {
+                    None => {
+                        println!("--crop needs x1,y1,x2,y2");
+                        return;
+                    }
+                    Some(Err(e)) => {
+                        println!("Couldn't understand the crop: {}", e);
+                        return;
+                    }
+                    Some(Ok(crop)) => options.crop = Some(crop)
+                }
+            }
+            _ => println!("Ignoring unknown option {}", flag)
+        }
+    }
+    for (part, diagonal) in [false, true].into_iter().enumerate() {
+        match read_input(filename, diagonal) {
+            Ok((lines, bounds)) => do_test(lines, bounds, &engine, &options, part + 1),
+            Err(e) => {
+                println!("Couldn't parse {}: {}", filename, e);
+                return;
+            }
+        }
+    }
 }
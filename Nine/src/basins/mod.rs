@@ -0,0 +1,274 @@
+use grid::{Grid, Neighbours};
+
+// Working out which cells belong to which basin, either by what's walled off from what or by where
+// the water runs
+
+struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> UnionFind {
+        UnionFind {
+            parent: (0..n).collect(),
+            size: vec![1; n]
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        // Point everything on the way at its grandparent so the paths stay short
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let mut a = self.find(a);
+        let mut b = self.find(b);
+        if a == b {
+            return;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BasinOptions {
+    // Anything at least this high is a wall between basins
+    pub wall_height: u32,
+    // Water can run diagonally as well
+    pub diagonal: bool,
+    // A flat patch that's lower than everything around it counts as one low point
+    pub plateaus: bool,
+}
+
+// The puzzle's rules
+impl Default for BasinOptions {
+    fn default() -> Self {
+        BasinOptions {
+            wall_height: 9,
+            diagonal: false,
+            plateaus: false
+        }
+    }
+}
+
+impl BasinOptions {
+    pub fn neighbours(&self, heights: &Grid<u32>, x: usize, y: usize) -> Neighbours {
+        if self.diagonal {
+            heights.neighbours8(x, y)
+        } else {
+            heights.neighbours4(x, y)
+        }
+    }
+
+    pub fn is_wall(&self, height: u32) -> bool {
+        height >= self.wall_height
+    }
+}
+
+pub struct BasinMap {
+    // Which basin each cell is in. Walls aren't in one
+    pub labels: Grid<Option<u32>>,
+    // sizes[label] is how many cells that basin has
+    pub sizes: Vec<usize>,
+}
+
+// Turns whatever we grouped cells by into labels numbered in the order we first come across them
+// reading the map top to bottom
+fn number_basins(heights: &Grid<u32>, mut group_of: impl FnMut(usize, usize) -> Option<usize>) -> BasinMap {
+    let mut label_of_group: Vec<Option<u32>> = vec![None; heights.len()];
+    let mut sizes = Vec::new();
+    let labels = Grid::from_fn(heights.width(), heights.height(), |x, y| {
+        let group = group_of(x, y)?;
+        let label = *label_of_group[group].get_or_insert_with(|| {
+            sizes.push(0);
+            sizes.len() as u32 - 1
+        });
+        sizes[label as usize] += 1;
+        Some(label)
+    });
+    BasinMap { labels, sizes }
+}
+
+// One pass over the map. Neighbouring cells that aren't walls get joined up and whatever ends up joined
+// together is one basin. That's the same as the flood fill when every basin is walled off and has one
+// low point, which the puzzle promises. Otherwise it goes by walls alone: low points that share a walled
+// area end up in one basin even where flood and drain split them at a ridge
+pub fn label_basins(heights: &Grid<u32>, options: &BasinOptions) -> BasinMap {
+    let width = heights.width();
+    let mut sets = UnionFind::new(heights.len());
+    for (x, y) in heights.positions() {
+        if options.is_wall(heights[(x, y)]) {
+            continue;
+        }
+        for (nx, ny) in options.neighbours(heights, x, y) {
+            if !options.is_wall(heights[(nx, ny)]) {
+                sets.union(y * width + x, ny * width + nx);
+            }
+        }
+    }
+    number_basins(heights, |x, y| {
+        if options.is_wall(heights[(x, y)]) {
+            return None;
+        }
+        Some(sets.find(y * width + x))
+    })
+}
+
+// Joins up neighbouring cells of the same height that aren't walls
+fn flat_patches(heights: &Grid<u32>, options: &BasinOptions) -> UnionFind {
+    let width = heights.width();
+    let mut sets = UnionFind::new(heights.len());
+    for (x, y) in heights.positions() {
+        let height = heights[(x, y)];
+        if options.is_wall(height) {
+            continue;
+        }
+        for (nx, ny) in options.neighbours(heights, x, y) {
+            if heights[(nx, ny)] == height {
+                sets.union(y * width + x, ny * width + nx);
+            }
+        }
+    }
+    sets
+}
+
+// Each low point as the cells it covers. Without plateaus a low point is one cell lower than all of its
+// neighbours. With them it's a flat patch lower than everything around its edge
+pub fn low_regions(heights: &Grid<u32>, options: &BasinOptions) -> Vec<Vec<(usize, usize)>> {
+    if !options.plateaus {
+        return heights.positions()
+            .filter(|(x, y)| {
+                let height = heights[(*x, *y)];
+                !options.is_wall(height) && options.neighbours(heights, *x, *y).all(|(nx, ny)| height < heights[(nx, ny)])
+            })
+            .map(|position| vec![position])
+            .collect();
+    }
+    let width = heights.width();
+    let mut sets = flat_patches(heights, options);
+    let mut is_low = vec![true; heights.len()];
+    for (x, y) in heights.positions() {
+        let height = heights[(x, y)];
+        let patch = sets.find(y * width + x);
+        if options.is_wall(height) {
+            is_low[patch] = false;
+            continue;
+        }
+        for (nx, ny) in options.neighbours(heights, x, y) {
+            if heights[(nx, ny)] < height {
+                is_low[patch] = false;
+            }
+        }
+    }
+    let mut region_of_patch: Vec<Option<usize>> = vec![None; heights.len()];
+    let mut regions: Vec<Vec<(usize, usize)>> = Vec::new();
+    for (x, y) in heights.positions() {
+        let patch = sets.find(y * width + x);
+        if !is_low[patch] {
+            continue;
+        }
+        let region = *region_of_patch[patch].get_or_insert_with(|| {
+            regions.push(Vec::new());
+            regions.len() - 1
+        });
+        regions[region].push((x, y));
+    }
+    regions
+}
+
+// Every cell goes in the basin of whichever low point its water ends up in. Water leaves a flat patch
+// by the lowest cell next to it, and a patch with nowhere lower to go is where water collects. Without
+// plateaus a flat bottom isn't a low point, same as in low_regions, so whatever drains into one isn't
+// in a basin
+pub fn drain(heights: &Grid<u32>, options: &BasinOptions) -> BasinMap {
+    let width = heights.width();
+    let mut sets = flat_patches(heights, options);
+    // Lowest cell next to each patch, indexed by the patch's root
+    let mut outlet: Vec<Option<usize>> = vec![None; heights.len()];
+    let mut patches = Vec::new();
+    for (x, y) in heights.positions() {
+        let height = heights[(x, y)];
+        if options.is_wall(height) {
+            continue;
+        }
+        let cell = y * width + x;
+        let patch = sets.find(cell);
+        if patch == cell {
+            patches.push(patch);
+        }
+        for (nx, ny) in options.neighbours(heights, x, y) {
+            let neighbour_height = heights[(nx, ny)];
+            let lower_than_outlet = outlet[patch].map(|o| neighbour_height < heights[(o % width, o / width)]).unwrap_or(true);
+            if neighbour_height < height && lower_than_outlet {
+                outlet[patch] = Some(ny * width + nx);
+            }
+        }
+    }
+    // Outlets always go downhill so working up from the bottom means the patch the water runs into
+    // already knows where it ends up
+    patches.sort_by_key(|patch| heights[(patch % width, patch / width)]);
+    let mut sink: Vec<usize> = (0..heights.len()).collect();
+    for patch in patches {
+        if let Some(outlet) = outlet[patch] {
+            sink[patch] = sink[sets.find(outlet)];
+        }
+    }
+    number_basins(heights, |x, y| {
+        if options.is_wall(heights[(x, y)]) {
+            return None;
+        }
+        let sink = sink[sets.find(y * width + x)];
+        if !options.plateaus && sets.size[sink] > 1 {
+            return None;
+        }
+        Some(sink)
+    })
+}
+
+// Cells lower than all their neighbours that are too high to be anything but wall
+pub fn walled_low_points(heights: &Grid<u32>, options: &BasinOptions) -> usize {
+    heights.positions()
+        .filter(|(x, y)| {
+            let height = heights[(*x, *y)];
+            options.is_wall(height) && options.neighbours(heights, *x, *y).all(|(nx, ny)| height < heights[(nx, ny)])
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted_sizes(basin_map: &BasinMap) -> Vec<usize> {
+        let mut sizes = basin_map.sizes.clone();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes
+    }
+
+    #[test]
+    fn union_only_splits_at_walls() {
+        // Two plateaus with a ridge of 2s between them that's still lower than the walls
+        let heights = Grid::parse_digits("9999999\n9112119\n9112119\n9999999")
+            .unwrap()
+            .map(|height| *height as u32);
+        let options = BasinOptions { plateaus: true, ..BasinOptions::default() };
+        assert_eq!(low_regions(&heights, &options).len(), 2);
+        assert_eq!(sorted_sizes(&drain(&heights, &options)), vec![6, 4]);
+        assert_eq!(sorted_sizes(&label_basins(&heights, &options)), vec![10]);
+        // With a wall for a ridge they agree
+        let walled = Grid::parse_digits("9999999\n9119119\n9119119\n9999999")
+            .unwrap()
+            .map(|height| *height as u32);
+        assert_eq!(sorted_sizes(&drain(&walled, &options)), vec![4, 4]);
+        assert_eq!(sorted_sizes(&label_basins(&walled, &options)), vec![4, 4]);
+    }
+}
@@ -1,236 +1,181 @@
-use std::cmp::Ordering;
-use std::num::ParseIntError;
-
-struct Grid {
-    values: Vec<Vec<(u32, bool)>>,
-    width: usize
+use grid::Grid;
+use crate::basins::{BasinMap, BasinOptions};
+use crate::render::Picture;
+use crate::terrain::TerrainReport;
+
+mod basins;
+mod render;
+mod terrain;
+
+struct HeightMap {
+    heights: Grid<u32>,
+    searched: Grid<bool>,
+    options: BasinOptions
 }
 
-impl Grid {
-    fn new() -> Grid {
-        return Grid {
-            values: vec![],
-            width: 0
+impl HeightMap {
+    fn new(heights: Grid<u32>, options: BasinOptions) -> HeightMap {
+        HeightMap {
+            searched: Grid::new(heights.width(), heights.height(), false),
+            heights,
+            options
         }
     }
-    fn add_row(&mut self, new_row: Vec<u32>) -> bool {
-        if self.values.is_empty() && !new_row.is_empty(){
-            self.width = new_row.len();
-        } else if new_row.len() != self.width || new_row.is_empty() {
-            return false;
-        }
-        let mut row = Vec::with_capacity(new_row.len());
-        for col in new_row {
-            row.push((col, false));
-        }
-        self.values.push(row);
-        true
-    }
-
 
-    fn get_value(&self, x: usize, y: usize) -> Option<u32> {
-        if let Some(row) = self.values.get(y) {
-            if let Some(value) = row.get(x) {
-                return Some((*value).0);
+    // Flood fill uphill from the cells of a low point. Uses its own stack since a big basin would blow
+    // the real one
+    fn calculate_basin_size(&mut self, low_region: &[(usize, usize)]) -> usize {
+        let mut count = 0;
+        let mut to_search = low_region.to_vec();
+        while let Some((x, y)) = to_search.pop() {
+            if self.searched[(x, y)] || self.options.is_wall(self.heights[(x, y)]) {
+                continue;
             }
-        }
-        None
-    }
-
-    fn position_searched(&self, x: usize, y: usize) -> bool {
-        if let Some(row) = self.values.get(y) {
-            if let Some(value) = row.get(x) {
-                return (*value).1;
+            // this position hasn't been searched so we can continue
+            self.searched[(x, y)] = true;
+            count += 1;
+            for (nx, ny) in self.options.neighbours(&self.heights, x, y) {
+                if self.heights[(x, y)] < self.heights[(nx, ny)] && !self.searched[(nx, ny)] {
+                    to_search.push((nx, ny));
+                }
             }
         }
-        false
+        count
     }
 
-    fn cmp_locations(&self, x1: usize, y1: usize, x2: usize, y2: usize) -> Option<std::cmp::Ordering> {
-        let location_one = match self.get_value(x1, y1) {
-            None => return None,
-            Some(location) => location
-        };
-
-        let location_two = match self.get_value(x2, y2) {
-            None => return None,
-            Some(location) => location
-        };
-        return Some(location_one.cmp(&location_two));
+    fn low_regions(&self) -> Vec<Vec<(usize, usize)>> {
+        basins::low_regions(&self.heights, &self.options)
     }
 
-    fn diff_locations(&self, x1: usize, y1: usize, x2: usize, y2: usize) -> Option<u32> {
-        let location_one = match self.get_value(x1, y1) {
-            None => return None,
-            Some(location) => location
-        };
-
-        let location_two = match self.get_value(x2, y2) {
-            None => return None,
-            Some(location) => location
-        };
-        return Some(location_one - location_two);
+    // A flat low region only counts once
+    fn find_low_point_values(&self) -> Vec<u32> {
+        self.low_regions().into_iter().map(|region| self.heights[region[0]]).collect()
     }
 
-    fn position_is_low_point(&self, x: usize, y:usize) -> Option<bool> {
-        if self.get_value(x, y).is_none() {
-            return None;
-        }
-        if x > 0 {
-            if let Some(ord) = self.cmp_locations(x,y, x-1, y) {
-                if !ord.is_lt() {
-                    return Some(false);
-                }
-            }
-        }
-        if let Some(ord) = self.cmp_locations(x,y, x+1, y) {
-            if !ord.is_lt() {
-                return Some(false);
-            }
-        }
-        if y > 0 {
-            if let Some(ord) = self.cmp_locations(x,y, x, y-1) {
-                if !ord.is_lt() {
-                    return Some(false);
-                }
-            }
-        }
-        if let Some(ord) = self.cmp_locations(x,y, x, y+1) {
-            if !ord.is_lt() {
-                return Some(false);
-            }
+    fn find_basin_sizes(&mut self) -> Vec<usize> {
+        let mut basin_sizes = Vec::new();
+        for region in self.low_regions() {
+            let size = self.calculate_basin_size(&region);
+            basin_sizes.push(size);
         }
-        Some(true)
+        basin_sizes
     }
 
-    fn set_position_searched(&mut self, x: usize, y:usize) -> Result<(),()> {
-        if let Some(row) = self.values.get_mut(y) {
-            if let Some(value) = row.get_mut(x) {
-                (*value).1 = true;
-                return Ok(());
-            }
-        }
-        return Err(());
+    fn label_basins(&self) -> BasinMap {
+        basins::label_basins(&self.heights, &self.options)
     }
 
-    fn calculate_basin_size(&mut self, x: usize, y:usize) -> usize {
-        if self.position_searched(x,y) {
-            return 0;
-        }
-        if let Some(value) = self.get_value(x,y) {
-            if value == 9 {
-                return 0;
-            }
-        } else {
-            panic!("This shouldn't happen");
-        }
-        // this position hasn't been searched so we can continue
-        let _ = self.set_position_searched(x,y);
-        let mut count = 1;
-        if x > 0 {
-            if let Some(ord) = self.cmp_locations(x,y, x-1, y) {
-                if ord.is_lt() {
-                    count += self.calculate_basin_size(x-1, y);
-                }
-            }
-        }
-        if let Some(ord) = self.cmp_locations(x,y, x+1, y) {
-            if ord.is_lt() {
-                count += self.calculate_basin_size(x+1, y);
-            }
-        }
-        if y > 0 {
-            if let Some(ord) = self.cmp_locations(x,y, x, y-1) {
-                if ord.is_lt() {
-                    count += self.calculate_basin_size(x, y-1);
-                }
-            }
-        }
-        if let Some(ord) = self.cmp_locations(x,y, x, y+1) {
-            if ord.is_lt() {
-                count += self.calculate_basin_size(x, y+1);
-            }
-        }
-        count
+    fn drain(&self) -> BasinMap {
+        basins::drain(&self.heights, &self.options)
     }
 
-    fn find_low_point_values(&self) -> Vec<u32> {
-        let mut low_points = Vec::new();
-        for x in 0..self.width {
-            for y in 0..self.values.len() {
-                if let Some(result) = self.position_is_low_point(x, y) {
-                    if result {
-                        match self.get_value(x, y) {
-                            None => {},
-                            Some(value) => low_points.push(value)
-                        };
-                    }
-                }
-            }
+    // Basins are outlined from their labels if we have them, otherwise from what the flood fill searched
+    fn draw(&self, basin_map: Option<&BasinMap>) -> Picture {
+        let low_points: Vec<(usize, usize)> = self.low_regions().into_iter().flatten().collect();
+        match basin_map {
+            None => render::draw_searched(&self.heights, &self.options, &low_points, &self.searched),
+            Some(basin_map) => render::draw_basins(&self.heights, &self.options, &low_points, basin_map)
         }
-        low_points
     }
 
-    fn find_basin_sizes(&mut self) -> Vec<usize> {
-        let mut basin_sizes = Vec::new();
-        for x in 0..self.width {
-            for y in 0..self.values.len() {
-                if let Some(result) = self.position_is_low_point(x, y) {
-                    if result {
-                        let size = self.calculate_basin_size(x,y);
-                        basin_sizes.push(size);
-                    }
-                }
-            }
-        }
-        basin_sizes
+    fn analyse_terrain(&self, basin_map: &BasinMap) -> TerrainReport {
+        terrain::analyse(&self.heights, basin_map, &self.options)
     }
 }
 
-fn read_input(filename: &str) -> Option<Grid> {
+// Basins as letters, going round the alphabet if there are lots of them. Walls are blank
+fn show_basins(basin_map: &BasinMap) -> String {
+    basin_map.labels.map(|label| match label {
+        None => ' ',
+        Some(label) => (b'a' + (label % 26) as u8) as char
+    }).to_string()
+}
+
+fn read_input(filename: &str, options: BasinOptions) -> Option<HeightMap> {
     let file_contents = match std::fs::read_to_string(filename) {
         Ok(fc) => fc,
         Err(_) => {
             return None;
         }
     };
-    let mut grid = Grid::new();
-    for (_, line) in file_contents.lines().enumerate() {
-        let mut row = Vec::new();
-        for (_, value) in line.chars().enumerate() {
-            let parsed = match value.to_digit(10) {
-                Some(p) => p,
-                None => {
-                    return None;
-                }
-            };
-            row.push(parsed);
-        }
-        grid.add_row(row);
-    }
-    Some(grid)
+    let heights = Grid::parse_digits(&file_contents).ok()?.map(|height| *height as u32);
+    Some(HeightMap::new(heights, options))
 }
 
-
-
-
 fn main() {
-    let mut grid = match read_input("input") {
+    // Nine [input] [flood|union|drain] [--wall height] [--diagonal] [--plateaus] [--show] [--terrain]
+    //      [--ansi] [--ppm file] [--scale pixels]
+    let args: Vec<String> = std::env::args().collect();
+    let mut positional = Vec::new();
+    let mut options = BasinOptions::default();
+    let mut show = false;
+    let mut terrain = false;
+    let mut ansi = false;
+    let mut ppm_file: Option<String> = None;
+    let mut scale = 4;
+    let mut arg_iter = args.iter().skip(1);
+    while let Some(arg) = arg_iter.next() {
+        match arg.as_str() {
+            "--wall" => {
+                options.wall_height = arg_iter.next().and_then(|h| h.parse().ok()).expect("--wall needs a height");
+            }
+            "--diagonal" => options.diagonal = true,
+            "--plateaus" => options.plateaus = true,
+            "--show" => show = true,
+            "--terrain" => terrain = true,
+            "--ansi" => ansi = true,
+            "--ppm" => ppm_file = Some(arg_iter.next().expect("--ppm needs a file name").clone()),
+            "--scale" => scale = arg_iter.next().and_then(|s| s.parse().ok()).expect("--scale needs a number of pixels"),
+            _ => positional.push(arg.as_str())
+        }
+    }
+    let mut height_map = match read_input(positional.first().copied().unwrap_or("input"), options) {
         None => panic!("Couldn't read input"),
-        Some(grid) => grid
+        Some(height_map) => height_map
     };
-    let low_points = grid.find_low_point_values();
-    let mut risk = low_points.len() as u32;
-    for point in low_points {
-        risk += point;
+    let low_points = height_map.find_low_point_values();
+    let risk = low_points.len() as u32 + low_points.iter().sum::<u32>();
+    let walled = basins::walled_low_points(&height_map.heights, &options);
+    if walled > 0 {
+        println!("{} low points are at or above the wall height of {} so they're left out of the risk", walled, options.wall_height);
+    }
+    // The flood fill is the original way and doesn't need basins to be walled off, so it stays the default
+    let basin_map = match positional.get(1).copied().unwrap_or("flood") {
+        "flood" => None,
+        "union" => Some(height_map.label_basins()),
+        "drain" => Some(height_map.drain()),
+        _ => panic!("The method has to be flood, union or drain")
+    };
+    if terrain {
+        // The flood fill doesn't label anything so the report goes by the walls
+        let report = match &basin_map {
+            None => height_map.analyse_terrain(&height_map.label_basins()),
+            Some(basin_map) => height_map.analyse_terrain(basin_map)
+        };
+        print!("{}", report.summary());
+    }
+    let mut basin_sizes = match &basin_map {
+        None => height_map.find_basin_sizes(),
+        Some(basin_map) => {
+            if show {
+                println!("{}", show_basins(basin_map));
+            }
+            basin_map.sizes.clone()
+        }
+    };
+    if ansi || ppm_file.is_some() {
+        let picture = height_map.draw(basin_map.as_ref());
+        if ansi {
+            print!("{}", picture.to_ansi());
+        }
+        if let Some(filename) = ppm_file {
+            if let Err(e) = picture.write_ppm(filename.as_str(), scale) {
+                println!("Couldn't write {}: {}", filename, e);
+            }
+        }
     }
-    let mut basin_sizes = grid.find_basin_sizes();
     basin_sizes.sort();
     basin_sizes.reverse();
-    let n = if basin_sizes.len() >= 3 {3} else {basin_sizes.len()};
-    let mut basin_size_score = 1;
-    for i in 0..n {
-        basin_size_score = basin_size_score * basin_sizes[i];
-    }
+    let basin_size_score: usize = basin_sizes.iter().take(3).product();
     println!("The risk is {} basins are {:?}, basin size score is {}", risk, basin_sizes, basin_size_score);
 }
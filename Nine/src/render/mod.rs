@@ -0,0 +1,110 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use grid::Grid;
+use crate::basins::{BasinMap, BasinOptions};
+
+type Colour = (u8, u8, u8);
+
+// What the height map looks like. Every cell has a colour and low points get marked on top of that
+pub struct Picture {
+    colours: Grid<Colour>,
+    low_points: Grid<bool>,
+}
+
+const LOW_POINT_COLOUR: Colour = (255, 0, 0);
+
+// deep water -> shallows -> grass -> sand -> snow as the height goes from 0 to max_height
+fn height_colour(height: u32, max_height: u32) -> Colour {
+    const STOPS: [(f64, f64, f64); 5] = [(0.0, 0.0, 110.0), (0.0, 130.0, 150.0), (40.0, 140.0, 40.0), (190.0, 170.0, 120.0), (255.0, 255.0, 255.0)];
+    if max_height == 0 {
+        return (0, 0, 110);
+    }
+    let position = height.min(max_height) as f64 / max_height as f64 * (STOPS.len() - 1) as f64;
+    let lower = (position.floor() as usize).min(STOPS.len() - 2);
+    let fraction = position - lower as f64;
+    let (r1, g1, b1) = STOPS[lower];
+    let (r2, g2, b2) = STOPS[lower + 1];
+    (
+        (r1 + (r2 - r1) * fraction) as u8,
+        (g1 + (g2 - g1) * fraction) as u8,
+        (b1 + (b2 - b1) * fraction) as u8,
+    )
+}
+
+// Steps round the colour wheel by the golden ratio so basins next to each other come out different.
+// Fairly saturated and full brightness so they stand out against the height colours
+fn basin_colour(label: u32) -> Colour {
+    let hue = (label as f64 * 0.618_033_988_75).fract() * 6.0;
+    let channel = |n: f64| {
+        let k = (n + hue) % 6.0;
+        (255.0 * (1.0 - 0.75 * k.min(4.0 - k).clamp(0.0, 1.0))) as u8
+    };
+    (channel(5.0), channel(3.0), channel(1.0))
+}
+
+// Cells are coloured by height. A cell on the edge of its basin, next to a cell that isn't in the same
+// one, is drawn in the basin's colour so each basin gets an outline. `basin_of` says which basin a
+// cell is in, if any
+fn draw(heights: &Grid<u32>, options: &BasinOptions, low_points: &[(usize, usize)], basin_of: impl Fn(usize, usize) -> Option<u32>) -> Picture {
+    let max_height = heights.iter().copied().max().unwrap_or(0);
+    let colours = Grid::from_fn(heights.width(), heights.height(), |x, y| {
+        match basin_of(x, y) {
+            Some(label) if options.neighbours(heights, x, y).any(|(nx, ny)| basin_of(nx, ny) != Some(label)) => basin_colour(label),
+            _ => height_colour(heights[(x, y)], max_height)
+        }
+    });
+    let mut marks = Grid::new(heights.width(), heights.height(), false);
+    for (x, y) in low_points {
+        marks[(*x, *y)] = true;
+    }
+    Picture { colours, low_points: marks }
+}
+
+pub fn draw_basins(heights: &Grid<u32>, options: &BasinOptions, low_points: &[(usize, usize)], basin_map: &BasinMap) -> Picture {
+    draw(heights, options, low_points, |x, y| basin_map.labels[(x, y)])
+}
+
+// The flood fill doesn't keep track of which basin is which, only what it's been through, so
+// everything it reached gets outlined in one colour
+pub fn draw_searched(heights: &Grid<u32>, options: &BasinOptions, low_points: &[(usize, usize)], searched: &Grid<bool>) -> Picture {
+    draw(heights, options, low_points, |x, y| if searched[(x, y)] { Some(0) } else { None })
+}
+
+impl Picture {
+    // Each cell is two characters wide so it comes out roughly square. Low points are drawn as ()
+    pub fn to_ansi(&self) -> String {
+        let mut ansi = String::new();
+        for y in 0..self.colours.height() {
+            for x in 0..self.colours.width() {
+                let (r, g, b) = self.colours[(x, y)];
+                let mark = if self.low_points[(x, y)] { "()" } else { "  " };
+                ansi.push_str(format!("\x1b[48;2;{};{};{}m\x1b[1;38;2;255;0;0m{}\x1b[0m", r, g, b, mark).as_str());
+            }
+            ansi.push('\n');
+        }
+        ansi
+    }
+
+    // Binary colour image with every cell `scale` pixels square. Low points get a red dot in the
+    // middle, which needs a scale of at least 3 to leave any of the cell's own colour showing
+    pub fn write_ppm(&self, filename: &str, scale: usize) -> std::io::Result<()> {
+        let scale = scale.max(1);
+        let mut writer = BufWriter::new(File::create(filename)?);
+        write!(writer, "P6\n{} {}\n255\n", self.colours.width() * scale, self.colours.height() * scale)?;
+        for y in 0..self.colours.height() * scale {
+            let mut row = Vec::with_capacity(self.colours.width() * scale * 3);
+            for x in 0..self.colours.width() * scale {
+                let (cell_x, cell_y) = (x / scale, y / scale);
+                let in_middle = |offset: usize| scale < 3 || (offset >= scale / 3 && offset < scale - scale / 3);
+                let (r, g, b) = if self.low_points[(cell_x, cell_y)] && in_middle(x % scale) && in_middle(y % scale) {
+                    LOW_POINT_COLOUR
+                } else {
+                    self.colours[(cell_x, cell_y)]
+                };
+                row.extend_from_slice(&[r, g, b]);
+            }
+            writer.write_all(&row)?;
+        }
+        writer.flush()
+    }
+}
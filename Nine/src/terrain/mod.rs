@@ -0,0 +1,205 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use grid::Grid;
+use crate::basins::{BasinMap, BasinOptions};
+
+// Shape of the land around the basins. Peaks, passes, where basins meet and how much rain each basin
+// would hold before it spills over
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasinStats {
+    pub label: u32,
+    pub cells: usize,
+    // The lowest cell and its height
+    pub floor: (usize, usize),
+    pub floor_height: u32,
+    // How high the water gets before it spills out of the basin
+    pub water_level: u32,
+    pub depth: u32,
+    // Units of water sitting on top of the cells once everything is filled
+    pub volume: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ridge {
+    // The two basins it separates, lower label first
+    pub basins: (u32, u32),
+    pub cells: Vec<(usize, usize)>,
+    // The lowest point along it, where water would cross first
+    pub pass_height: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TerrainReport {
+    pub maxima: Vec<(usize, usize)>,
+    pub saddles: Vec<(usize, usize)>,
+    pub basins: Vec<BasinStats>,
+    pub ridges: Vec<Ridge>,
+    pub total_water: u64,
+}
+
+// Cells higher than every neighbour
+fn local_maxima(heights: &Grid<u32>, options: &BasinOptions) -> Vec<(usize, usize)> {
+    heights.positions()
+        .filter(|(x, y)| options.neighbours(heights, *x, *y).all(|(nx, ny)| heights[(nx, ny)] < heights[(*x, *y)]))
+        .collect()
+}
+
+// The eight cells round a cell, in order going round it
+const RING: [(i64, i64); 8] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+
+// Going round a saddle the neighbours go up, down, up, down. Neighbours at the same height don't say
+// either way so they're left out, and cells on the edge don't have a full ring to look at
+fn saddle_points(heights: &Grid<u32>) -> Vec<(usize, usize)> {
+    let mut saddles = Vec::new();
+    for (x, y) in heights.positions() {
+        if x == 0 || y == 0 || x + 1 == heights.width() || y + 1 == heights.height() {
+            continue;
+        }
+        let height = heights[(x, y)];
+        let higher: Vec<bool> = RING.iter()
+            .map(|(dx, dy)| heights[((x as i64 + dx) as usize, (y as i64 + dy) as usize)])
+            .filter(|h| *h != height)
+            .map(|h| h > height)
+            .collect();
+        let changes = (0..higher.len()).filter(|i| higher[*i] != higher[(i + 1) % higher.len()]).count();
+        if changes >= 4 {
+            saddles.push((x, y));
+        }
+    }
+    saddles
+}
+
+// How high water settles on each cell when it rains everywhere and runs off the edges of the map.
+// Works in from the edge lowest first, so the first time we reach a cell we've come over the lowest
+// rim that's between it and the edge
+fn water_levels(heights: &Grid<u32>, options: &BasinOptions) -> Grid<u32> {
+    let width = heights.width();
+    let mut levels = heights.clone();
+    let mut reached = Grid::new(width, heights.height(), false);
+    let mut to_visit = BinaryHeap::new();
+    for (x, y) in heights.positions() {
+        if x == 0 || y == 0 || x + 1 == width || y + 1 == heights.height() {
+            reached[(x, y)] = true;
+            to_visit.push(Reverse((heights[(x, y)], x, y)));
+        }
+    }
+    while let Some(Reverse((level, x, y))) = to_visit.pop() {
+        for (nx, ny) in options.neighbours(heights, x, y) {
+            if reached[(nx, ny)] {
+                continue;
+            }
+            reached[(nx, ny)] = true;
+            let neighbour_level = level.max(heights[(nx, ny)]);
+            levels[(nx, ny)] = neighbour_level;
+            to_visit.push(Reverse((neighbour_level, nx, ny)));
+        }
+    }
+    levels
+}
+
+fn basin_stats(heights: &Grid<u32>, basin_map: &BasinMap, levels: &Grid<u32>) -> Vec<BasinStats> {
+    let mut stats: Vec<BasinStats> = basin_map.sizes.iter().enumerate().map(|(label, cells)| BasinStats {
+        label: label as u32,
+        cells: *cells,
+        floor: (0, 0),
+        floor_height: u32::MAX,
+        water_level: 0,
+        depth: 0,
+        volume: 0,
+    }).collect();
+    for ((x, y), label) in basin_map.labels.cells() {
+        let basin = match label {
+            None => continue,
+            Some(label) => &mut stats[*label as usize]
+        };
+        let height = heights[(x, y)];
+        if height < basin.floor_height {
+            basin.floor = (x, y);
+            basin.floor_height = height;
+            basin.water_level = levels[(x, y)];
+        }
+        basin.volume += (levels[(x, y)] - height) as u64;
+    }
+    for basin in stats.iter_mut() {
+        basin.depth = basin.water_level - basin.floor_height;
+    }
+    stats
+}
+
+// A ridge is every cell on the edge of one basin next to another, plus any wall cells that touch both.
+// Water crossing from one cell to the next has to get over the higher of the two so that's what the
+// pass height goes by
+fn ridges(heights: &Grid<u32>, basin_map: &BasinMap, options: &BasinOptions) -> Vec<Ridge> {
+    let mut between: BTreeMap<(u32, u32), Ridge> = BTreeMap::new();
+    let mut add = |a: u32, b: u32, position: (usize, usize), crossing: u32| {
+        let basins = (a.min(b), a.max(b));
+        let ridge = between.entry(basins).or_insert(Ridge { basins, cells: Vec::new(), pass_height: u32::MAX });
+        if ridge.cells.last() != Some(&position) {
+            ridge.cells.push(position);
+        }
+        ridge.pass_height = ridge.pass_height.min(crossing);
+    };
+    for ((x, y), label) in basin_map.labels.cells() {
+        let height = heights[(x, y)];
+        match label {
+            Some(label) => {
+                for (nx, ny) in options.neighbours(heights, x, y) {
+                    match basin_map.labels[(nx, ny)] {
+                        Some(other) if other != *label => add(*label, other, (x, y), height.max(heights[(nx, ny)])),
+                        _ => {}
+                    }
+                }
+            }
+            None => {
+                let mut touching: Vec<u32> = options.neighbours(heights, x, y)
+                    .filter_map(|(nx, ny)| basin_map.labels[(nx, ny)])
+                    .collect();
+                touching.sort_unstable();
+                touching.dedup();
+                for (i, a) in touching.iter().enumerate() {
+                    for b in touching[i + 1..].iter() {
+                        add(*a, *b, (x, y), height);
+                    }
+                }
+            }
+        }
+    }
+    between.into_values().collect()
+}
+
+pub fn analyse(heights: &Grid<u32>, basin_map: &BasinMap, options: &BasinOptions) -> TerrainReport {
+    let levels = water_levels(heights, options);
+    let total_water = heights.iter().zip(levels.iter()).map(|(h, l)| (l - h) as u64).sum();
+    TerrainReport {
+        maxima: local_maxima(heights, options),
+        saddles: saddle_points(heights),
+        basins: basin_stats(heights, basin_map, &levels),
+        ridges: ridges(heights, basin_map, options),
+        total_water
+    }
+}
+
+// Only this many of the biggest basins and lowest passes get listed
+const SUMMARY_LENGTH: usize = 10;
+
+impl TerrainReport {
+    pub fn summary(&self) -> String {
+        let mut summary = format!("{} local maxima, {} saddle points, {} basins and {} ridges between them\n",
+            self.maxima.len(), self.saddles.len(), self.basins.len(), self.ridges.len());
+        summary.push_str(format!("It would hold {} units of water altogether\n", self.total_water).as_str());
+        let mut biggest: Vec<&BasinStats> = self.basins.iter().collect();
+        biggest.sort_by_key(|basin| Reverse(basin.cells));
+        for basin in biggest.iter().take(SUMMARY_LENGTH) {
+            summary.push_str(format!("Basin {} has {} cells, floor {} at {:?}, depth {} holding {} water\n",
+                basin.label, basin.cells, basin.floor_height, basin.floor, basin.depth, basin.volume).as_str());
+        }
+        let mut lowest: Vec<&Ridge> = self.ridges.iter().collect();
+        lowest.sort_by_key(|ridge| ridge.pass_height);
+        for ridge in lowest.iter().take(SUMMARY_LENGTH) {
+            summary.push_str(format!("Basins {} and {} meet along {} cells, lowest at height {}\n",
+                ridge.basins.0, ridge.basins.1, ridge.cells.len(), ridge.pass_height).as_str());
+        }
+        summary
+    }
+}
@@ -0,0 +1,204 @@
+use crate::Alignment;
+use crate::index::PositionIndex;
+
+fn distance(a: i64, b: i64) -> u128 {
+    (a as i128 - b as i128).unsigned_abs()
+}
+
+// Costs saturate at u128::MAX rather than overflowing, so a cost of u128::MAX means "at least that
+// much". Anything implementing this should do the same. Once costs saturate they all look equally
+// bad, so the best position is only trustworthy while its cost is under the limit
+pub trait CostFunction {
+    // Fuel for the crab at `crab` (its index in the input) to move `distance` steps
+    fn crab_cost(&self, crab: usize, distance: u128) -> u128;
+
+    // True when the total cost is convex in the target position, which lets the optimiser use
+    // ternary search. Holds whenever each crab's cost is convex and never goes down with distance
+    fn is_convex(&self) -> bool;
+
+    fn total_cost(&self, target: i64, positions: &[i64]) -> u128 {
+        positions.iter().enumerate()
+            .map(|(crab, p)| self.crab_cost(crab, distance(*p, target)))
+            .fold(0, u128::saturating_add)
+    }
+
+    // The total from the prefix sum index in O(log n), for the costs that can work that way
+    fn indexed_cost(&self, _target: i64, _index: &PositionIndex) -> Option<u128> {
+        None
+    }
+}
+
+// Uses the index when the cost supports it and falls back to adding up every crab
+struct Evaluator<'a, C: CostFunction + ?Sized> {
+    positions: &'a [i64],
+    cost: &'a C,
+    index: Option<PositionIndex>,
+}
+
+impl<'a, C: CostFunction + ?Sized> Evaluator<'a, C> {
+    fn new(positions: &'a [i64], cost: &'a C) -> Evaluator<'a, C> {
+        Evaluator {
+            positions,
+            cost,
+            index: PositionIndex::new(positions)
+        }
+    }
+
+    fn cost_at(&self, target: i64) -> u128 {
+        self.index.as_ref()
+            .and_then(|index| self.cost.indexed_cost(target, index))
+            .unwrap_or_else(|| self.cost.total_cost(target, self.positions))
+    }
+}
+
+pub struct Linear;
+
+impl CostFunction for Linear {
+    fn indexed_cost(&self, target: i64, index: &PositionIndex) -> Option<u128> {
+        index.linear_cost(target)
+    }
+
+    fn crab_cost(&self, _: usize, distance: u128) -> u128 {
+        distance
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+}
+
+// 1 + 2 + ... + distance
+pub struct Triangular;
+
+impl CostFunction for Triangular {
+    fn indexed_cost(&self, target: i64, index: &PositionIndex) -> Option<u128> {
+        index.triangular_cost(target)
+    }
+
+    // Halve whichever of d and d + 1 is even first so only a cost that really doesn't fit saturates
+    fn crab_cost(&self, _: usize, distance: u128) -> u128 {
+        if distance.is_multiple_of(2) {
+            (distance / 2).saturating_mul(distance.saturating_add(1))
+        } else {
+            distance.saturating_mul(distance / 2 + 1)
+        }
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+}
+
+pub struct Quadratic;
+
+impl CostFunction for Quadratic {
+    fn indexed_cost(&self, target: i64, index: &PositionIndex) -> Option<u128> {
+        index.quadratic_cost(target)
+    }
+
+    fn crab_cost(&self, _: usize, distance: u128) -> u128 {
+        distance.saturating_mul(distance)
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+}
+
+// Scales another cost by a per crab weight. Crabs without a weight count as 1
+pub struct Weighted<C: CostFunction> {
+    pub inner: C,
+    pub weights: Vec<u128>,
+}
+
+impl<C: CostFunction> CostFunction for Weighted<C> {
+    fn crab_cost(&self, crab: usize, distance: u128) -> u128 {
+        self.weights.get(crab).copied().unwrap_or(1).saturating_mul(self.inner.crab_cost(crab, distance))
+    }
+
+    fn is_convex(&self) -> bool {
+        self.inner.is_convex()
+    }
+}
+
+// Any closure. We can't tell if it's convex so the caller has to say, and it needs to saturate too
+pub struct Custom<F: Fn(usize, u128) -> u128> {
+    pub cost: F,
+    pub convex: bool,
+}
+
+impl<F: Fn(usize, u128) -> u128> CostFunction for Custom<F> {
+    fn crab_cost(&self, crab: usize, distance: u128) -> u128 {
+        (self.cost)(crab, distance)
+    }
+
+    fn is_convex(&self) -> bool {
+        self.convex
+    }
+}
+
+fn position_range(positions: &[i64]) -> (i64, i64) {
+    let min = *positions.iter().min().unwrap_or(&0);
+    let max = *positions.iter().max().unwrap_or(&0);
+    (min, max)
+}
+
+pub fn exhaustive_search(positions: &[i64], cost: &(impl CostFunction + ?Sized)) -> Alignment {
+    let evaluator = Evaluator::new(positions, cost);
+    let (min, max) = position_range(positions);
+    let mut best = Alignment { position: min, cost: evaluator.cost_at(min) };
+    for position in min..=max {
+        let candidate = evaluator.cost_at(position);
+        if candidate < best.cost {
+            best = Alignment { position, cost: candidate };
+        }
+    }
+    best
+}
+
+// Integer ternary search. For a convex function equal values at the two probes mean the minimum is
+// between them so flat stretches don't throw it off
+pub fn ternary_search(positions: &[i64], cost: &(impl CostFunction + ?Sized)) -> Alignment {
+    let evaluator = Evaluator::new(positions, cost);
+    let (mut low, mut high) = position_range(positions);
+    while high - low > 2 {
+        let third = (high - low) / 3;
+        let left = low + third;
+        let right = high - third;
+        let left_cost = evaluator.cost_at(left);
+        let right_cost = evaluator.cost_at(right);
+        if left_cost < right_cost {
+            high = right - 1;
+        } else if left_cost > right_cost {
+            low = left + 1;
+        } else {
+            low = left;
+            high = right;
+        }
+    }
+    let mut best = Alignment { position: low, cost: evaluator.cost_at(low) };
+    for position in low + 1..=high {
+        let candidate = evaluator.cost_at(position);
+        if candidate < best.cost {
+            best = Alignment { position, cost: candidate };
+        }
+    }
+    best
+}
+
+// The best place for the crabs to line up under any cost. Only positions between the outermost crabs
+// are considered since moving past them can't help a cost that grows with distance
+pub fn optimise(positions: &[i64], cost: &(impl CostFunction + ?Sized)) -> Alignment {
+    if cost.is_convex() {
+        ternary_search(positions, cost)
+    } else {
+        exhaustive_search(positions, cost)
+    }
+}
+
+// Cost at every position from the leftmost crab to the rightmost one
+pub fn cost_curve(positions: &[i64], cost: &(impl CostFunction + ?Sized)) -> Vec<(i64, u128)> {
+    let evaluator = Evaluator::new(positions, cost);
+    let (min, max) = position_range(positions);
+    (min..=max).map(|position| (position, evaluator.cost_at(position))).collect()
+}
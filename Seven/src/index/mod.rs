@@ -0,0 +1,118 @@
+// Sorted positions with running sums of p and p² so the linear, quadratic and triangular costs at
+// any target are a binary search and some arithmetic instead of a walk over every crab
+pub struct PositionIndex {
+    sorted: Vec<i64>,
+    // prefix_sums[k] is the sum of the first k sorted positions
+    prefix_sums: Vec<i128>,
+    total_squares: i128,
+}
+
+impl PositionIndex {
+    // None when the positions are spread so wide that the sums might not fit in an i128. Every term
+    // we work with is at most n * max² so that's what gets checked, with some headroom
+    pub fn new(positions: &[i64]) -> Option<PositionIndex> {
+        let mut sorted = positions.to_vec();
+        sorted.sort_unstable();
+        let max_abs = sorted.iter().map(|p| p.unsigned_abs() as i128).max().unwrap_or(0);
+        max_abs.checked_mul(max_abs)?
+            .checked_mul(sorted.len() as i128)?
+            .checked_mul(4)?;
+        let mut prefix_sums = Vec::with_capacity(sorted.len() + 1);
+        let mut sum: i128 = 0;
+        prefix_sums.push(sum);
+        for position in sorted.iter() {
+            sum += *position as i128;
+            prefix_sums.push(sum);
+        }
+        let total_squares = sorted.iter().map(|p| *p as i128 * *p as i128).sum();
+        Some(PositionIndex {
+            sorted,
+            prefix_sums,
+            total_squares
+        })
+    }
+
+    pub fn min(&self) -> i64 {
+        *self.sorted.first().unwrap_or(&0)
+    }
+
+    pub fn max(&self) -> i64 {
+        *self.sorted.last().unwrap_or(&0)
+    }
+
+    fn len(&self) -> i128 {
+        self.sorted.len() as i128
+    }
+
+    fn total(&self) -> i128 {
+        *self.prefix_sums.last().unwrap_or(&0)
+    }
+
+    // Targets outside min..=max could overflow so they aren't answered
+    fn in_range(&self, target: i64) -> bool {
+        !self.sorted.is_empty() && target >= self.min() && target <= self.max()
+    }
+
+    // Σ|p - target|
+    pub fn linear_cost(&self, target: i64) -> Option<u128> {
+        if !self.in_range(target) {
+            return None;
+        }
+        let target = target as i128;
+        let below = self.sorted.partition_point(|p| (*p as i128) <= target);
+        let below_sum = self.prefix_sums[below];
+        let above_sum = self.total() - below_sum;
+        let below_count = below as i128;
+        let above_count = self.len() - below_count;
+        Some((target * below_count - below_sum + above_sum - target * above_count) as u128)
+    }
+
+    // Σ(p - target)²
+    pub fn quadratic_cost(&self, target: i64) -> Option<u128> {
+        if !self.in_range(target) {
+            return None;
+        }
+        let target = target as i128;
+        Some((self.total_squares - 2 * target * self.total() + self.len() * target * target) as u128)
+    }
+
+    // Σ d(d + 1) / 2 = (Σd² + Σd) / 2
+    pub fn triangular_cost(&self, target: i64) -> Option<u128> {
+        Some((self.quadratic_cost(target)? + self.linear_cost(target)?) / 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cost::{CostFunction, Linear, Quadratic, Triangular};
+
+    #[test]
+    fn indexed_costs_match_adding_up_every_crab() {
+        let inputs: [&[i64]; 5] = [
+            &[16, 1, 2, 0, 4, 2, 7, 1, 2, 14],
+            &[5],
+            &[3, 3, 3],
+            &[-7, -2, -2, 0, 3, 9, 9, 9],
+            &[-300, 12, 12, 13, 250],
+        ];
+        for positions in inputs {
+            let index = PositionIndex::new(positions).expect("These all fit");
+            let (min, max) = (index.min(), index.max());
+            for target in min - 1..=max + 1 {
+                let costs = [
+                    (index.linear_cost(target), Linear.total_cost(target, positions)),
+                    (index.triangular_cost(target), Triangular.total_cost(target, positions)),
+                    (index.quadratic_cost(target), Quadratic.total_cost(target, positions)),
+                ];
+                for (indexed, walked) in costs {
+                    match indexed {
+                        Some(cost) => assert_eq!(cost, walked, "{:?} at {}", positions, target),
+                        // Past the crabs the evaluator walks instead
+                        None => assert!(target < min || target > max, "{:?} at {}", positions, target),
+                    }
+                }
+            }
+        }
+    }
+}
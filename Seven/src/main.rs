@@ -1,62 +1,194 @@
-use std::collections::HashMap;
-use std::error::Error;
-use std::num::ParseIntError;
-
-fn median(values: &Vec<f32>) -> f32 {
-    let mut sorted_values = values.clone();
-    sorted_values.sort_by(|a, b| a.partial_cmp(b).unwrap());
-    let length = sorted_values.len();
-    return if length % 2 == 0 {
-        //even number
-        let upper_middle = (length / 2);
-        if upper_middle == 0 {
-            return 0f32;
-        }
-        let lower_middle = upper_middle -1;
-        (sorted_values[lower_middle] + sorted_values[upper_middle]) / 2f32
-    } else {
-        let middle_index = (length as f32 / 2f32).ceil() as usize;
-        sorted_values[middle_index]
-    }
+use int_list::ListParseError;
+use crate::cost::{CostFunction, Custom, Linear, Quadratic, Triangular, Weighted};
+
+mod cost;
+mod index;
+
+// Everything is integer maths so it's exact as long as the totals fit in a u128. Linear, triangular
+// and quadratic costs are at most n * d * (d + 1) for a spread of d, and check_spread turns away
+// inputs where that doesn't fit rather than letting it overflow. Weighted and custom costs can grow
+// faster than that, so they saturate instead (see CostFunction)
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Alignment {
+    pub position: i64,
+    pub cost: u128,
 }
 
+fn median(values: &[i64]) -> i64 {
+    let mut sorted_values = values.to_vec();
+    sorted_values.sort_unstable();
+    if sorted_values.is_empty() {
+        return 0;
+    }
+    // Any point between the two middle values is just as good for linear fuel so take the lower one
+    sorted_values[(sorted_values.len() - 1) / 2]
+}
 
-fn read_input(filename: &str) -> Vec<f32> {
+fn read_input(filename: &str) -> Result<Vec<i64>, ListParseError> {
     let file_contents = std::fs::read_to_string(filename).expect("Couldn't read file");
-    let mut values = Vec::new();
-    for val_str in file_contents.split(",") {
-        match val_str.parse::<f32>() {
-            Ok(v) => values.push(v),
-            Err(_) => panic!("Couldn't parse value")
-        }
+    int_list::parse_list::<i64>(file_contents.as_str())
+}
+
+// The triangular solver looks one step past the outermost crabs, so this leaves room to spare
+fn check_spread(values: &[i64]) -> Result<(), String> {
+    let min = values.iter().min().copied().unwrap_or(0) as i128;
+    let max = values.iter().max().copied().unwrap_or(0) as i128;
+    let furthest = (max - min) as u128 + 2;
+    furthest.checked_mul(furthest + 1)
+        .and_then(|cost| cost.checked_mul(values.len() as u128))
+        .map(|_| ())
+        .ok_or_else(|| format!("The crabs are spread from {} to {} which is too far for the fuel to add up exactly", min, max))
+}
+
+fn solve_linear(values: &[i64]) -> Alignment {
+    let position = median(values);
+    Alignment {
+        position,
+        cost: Linear.total_cost(position, values)
     }
-    values
 }
 
-fn calculate_fuel(value: f32, test_values: &Vec<f32>) -> f32 {
-    let mut fuel = 0f32;
-    for v in test_values {
-        fuel += (*v - value).abs()
+// How the triangular answer was found. The search near the mean should always do it, so needing
+// to check everything means the maths above it is wrong somewhere
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Search {
+    NearMean,
+    Exhaustive,
+}
+
+// The best position for triangular fuel is always within half a step of the mean. Every integer in
+// that window gets checked and then we make sure the answer really is a minimum by looking either
+// side of it. The cost is convex so a local minimum is the global one. The mean is never outside the
+// crabs so neither is the window, which keeps it inside an i64 even when the crabs are right at the
+// ends
+fn solve_triangular(values: &[i64]) -> Result<(Alignment, Search), String> {
+    let (min, max) = match (values.iter().min(), values.iter().max()) {
+        (Some(min), Some(max)) => (*min as i128, *max as i128),
+        _ => return Ok((Alignment { position: 0, cost: 0 }, Search::NearMean))
+    };
+    let n = values.len() as i128;
+    let sum: i128 = values.iter().map(|v| *v as i128).sum();
+    // mean ± 1/2 as (2 * sum ∓ n) / 2n, rounded outwards
+    let low = (2 * sum - n).div_euclid(2 * n).max(min);
+    let high = (2 * sum + n + 2 * n - 1).div_euclid(2 * n).min(max);
+    let mut best: Option<Alignment> = None;
+    for position in low..=high {
+        let position = i64::try_from(position).map_err(|_| format!("Position {} doesn't fit in an i64", position))?;
+        let cost = Triangular.total_cost(position, values);
+        if best.is_none_or(|b| cost < b.cost) {
+            best = Some(Alignment { position, cost });
+        }
+    }
+    let best = best.ok_or("There weren't any positions near the mean to try")?;
+    // Past the end of an i64 there's nothing to compare against
+    let no_better = |neighbour: Option<i64>| neighbour.is_none_or(|p| Triangular.total_cost(p, values) >= best.cost);
+    if !(no_better(best.position.checked_sub(1)) && no_better(best.position.checked_add(1))) {
+        return Ok((cost::exhaustive_search(values, &Triangular), Search::Exhaustive));
     }
-    fuel
+    Ok((best, Search::NearMean))
 }
 
-fn calculate_fuel_exp(value: f32, test_values: &Vec<f32>) -> f64 {
-    let mut fuel = 0f64;
-    for v in test_values {
-        let steps = (*v - value).abs();
-        fuel += (steps * (steps + 1f32) / 2f32) as f64;
+fn print_alignment(name: &str, alignment: Alignment) {
+    if alignment.cost == u128::MAX {
+        println!("{} fuel is more than a u128 can hold everywhere, so position {} may not be the best", name, alignment.position);
+        return;
     }
-    fuel
+    println!("{} fuel is {} at position {}", name, alignment.cost, alignment.position);
+}
+
+// Picks a cost function from the arguments starting at `args[start]`
+fn cost_from_args(args: &[String], start: usize) -> Result<(&'static str, Box<dyn CostFunction>), String> {
+    let cost: (&'static str, Box<dyn CostFunction>) = match args.get(start).map(|c| c.as_str()) {
+        Some("linear") => ("Linear", Box::new(Linear)),
+        Some("triangular") => ("Triangular", Box::new(Triangular)),
+        Some("quadratic") => ("Quadratic", Box::new(Quadratic)),
+        Some("cubic") => ("Cubic", Box::new(Custom { cost: |_, d: u128| d.saturating_mul(d).saturating_mul(d), convex: true })),
+        Some("capped") => {
+            // Nobody pays more than the limit, which isn't convex so every position gets checked
+            let limit = match args.get(start + 1).map(|l| l.parse::<u128>()) {
+                None => 10,
+                Some(Ok(limit)) => limit,
+                Some(Err(_)) => return Err("Couldn't parse the limit".to_string())
+            };
+            ("Capped", Box::new(Custom { cost: move |_, d: u128| d.min(limit), convex: false }))
+        }
+        Some("weighted") => {
+            let weights_file = args.get(start + 1).ok_or("Need a file of weights, one per crab")?;
+            let weights_contents = std::fs::read_to_string(weights_file).map_err(|e| e.to_string())?;
+            let weights = int_list::parse_list::<u128>(weights_contents.as_str()).map_err(|e| e.to_string())?;
+            ("Weighted triangular", Box::new(Weighted { inner: Triangular, weights }))
+        }
+        Some(other) => return Err(format!("Don't know the {} cost", other)),
+        None => return Err("Need a cost".to_string())
+    };
+    Ok(cost)
 }
 
 fn main() {
-    let mut values = read_input("input");
-    let median = median(& values);
-    let fuel = calculate_fuel(median, & values);
-    let average = values.iter().sum::<f32>() / values.len() as f32;
-    let fuel_exp_low = calculate_fuel_exp(average.floor(), & values);
-    let fuel_exp_high = calculate_fuel_exp(average.ceil(), & values);
-    let fuel_exp = if fuel_exp_low < fuel_exp_high {fuel_exp_low} else {fuel_exp_high};
-    println!("Fuel required is {} exp is {}", fuel, fuel_exp);
-}
\ No newline at end of file
+    // Seven [input] [linear|triangular|quadratic|cubic|capped <limit>|weighted <weights file>]
+    // Seven [input] curve <cost> prints the cost at every position as csv
+    let args: Vec<String> = std::env::args().collect();
+    let values = match read_input(args.get(1).map(|f| f.as_str()).unwrap_or("input")) {
+        Ok(values) => values,
+        Err(e) => panic!("Couldn't read the input, {}", e)
+    };
+    if let Err(e) = check_spread(&values) {
+        println!("{}", e);
+        return;
+    }
+    match args.get(2).map(|c| c.as_str()) {
+        None => {
+            let linear = solve_linear(&values);
+            let (triangular, search) = match solve_triangular(&values) {
+                Ok(solution) => solution,
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                }
+            };
+            if search == Search::Exhaustive {
+                println!("The search near the mean didn't find a minimum so every position was checked");
+            }
+            println!("Fuel required is {} at position {}, exp is {} at position {}", linear.cost, linear.position, triangular.cost, triangular.position);
+        }
+        Some("curve") => match cost_from_args(&args, 3) {
+            Ok((_, cost)) => {
+                println!("position,cost");
+                for (position, cost) in cost::cost_curve(&values, cost.as_ref()) {
+                    println!("{},{}", position, cost);
+                }
+            }
+            Err(e) => println!("{}", e)
+        },
+        Some(_) => match cost_from_args(&args, 2) {
+            Ok((name, cost)) => print_alignment(name, cost::optimise(&values, cost.as_ref())),
+            Err(e) => println!("{}", e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triangular_sample() {
+        let values = [16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+        assert_eq!(solve_triangular(&values), Ok((Alignment { position: 5, cost: 168 }, Search::NearMean)));
+    }
+
+    #[test]
+    fn triangular_at_the_ends_of_an_i64() {
+        let cases: [(&[i64], Alignment); 4] = [
+            (&[i64::MAX, i64::MAX], Alignment { position: i64::MAX, cost: 0 }),
+            (&[i64::MIN, i64::MIN], Alignment { position: i64::MIN, cost: 0 }),
+            (&[i64::MAX - 1, i64::MAX], Alignment { position: i64::MAX - 1, cost: 1 }),
+            (&[i64::MIN, i64::MIN + 3], Alignment { position: i64::MIN + 1, cost: 4 }),
+        ];
+        for (values, expected) in cases {
+            assert!(check_spread(values).is_ok());
+            assert_eq!(solve_triangular(values), Ok((expected, Search::NearMean)), "{:?}", values);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::io::Write;
+use crate::population::{PopulationModel, Species};
+
+// Everything here runs off the transition matrices so it works for any species layout. Counts are
+// u128 and we stop with an error rather than wrapping when they run out
+
+pub struct DayCounts {
+    pub day: usize,
+    // One set of buckets per species, in the same order as the model
+    pub species: Vec<Vec<u128>>,
+}
+
+impl DayCounts {
+    pub fn total(&self) -> u128 {
+        self.species.iter().flatten().sum()
+    }
+}
+
+fn apply(matrix: &[Vec<u64>], buckets: &[u128]) -> Option<Vec<u128>> {
+    let mut next = Vec::with_capacity(buckets.len());
+    for row in matrix {
+        let mut count: u128 = 0;
+        for (weight, bucket) in row.iter().zip(buckets) {
+            if *weight != 0 {
+                count = count.checked_add((*weight as u128).checked_mul(*bucket)?)?;
+            }
+        }
+        next.push(count);
+    }
+    Some(next)
+}
+
+fn starting_buckets(species: &Species) -> Vec<u128> {
+    species.buckets().iter().map(|c| *c as u128).collect()
+}
+
+// Bucket counts for every day from today up to and including `days`
+pub fn time_series(model: &PopulationModel, days: usize) -> Result<Vec<DayCounts>, String> {
+    let matrices: Vec<Vec<Vec<u64>>> = model.species.iter().map(|s| s.transition_matrix()).collect();
+    let mut series = Vec::with_capacity(days + 1);
+    series.push(DayCounts {
+        day: 0,
+        species: model.species.iter().map(starting_buckets).collect()
+    });
+    for day in 1..=days {
+        let previous = &series[day - 1];
+        let mut species = Vec::with_capacity(matrices.len());
+        for (matrix, buckets) in matrices.iter().zip(previous.species.iter()) {
+            species.push(apply(matrix, buckets).ok_or(format!("The counts got too big to store on day {}", day))?);
+        }
+        series.push(DayCounts { day, species });
+    }
+    Ok(series)
+}
+
+// One row per day and species. Species have different bucket counts so short rows are padded out
+pub fn write_csv(model: &PopulationModel, series: &[DayCounts], writer: &mut impl Write) -> std::io::Result<()> {
+    let num_buckets = model.species.iter().map(|s| s.buckets().len()).max().unwrap_or(0);
+    write!(writer, "day,species,total")?;
+    for bucket in 0..num_buckets {
+        write!(writer, ",bucket_{}", bucket)?;
+    }
+    writeln!(writer)?;
+    for day in series {
+        for (species, buckets) in model.species.iter().zip(day.species.iter()) {
+            write!(writer, "{},{},{}", day.day, species.params.name, buckets.iter().sum::<u128>())?;
+            for bucket in 0..num_buckets {
+                match buckets.get(bucket) {
+                    None => write!(writer, ",")?,
+                    Some(count) => write!(writer, ",{}", count)?
+                }
+            }
+            writeln!(writer)?;
+        }
+    }
+    Ok(())
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(format!("\\u{:04x}", c as u32).as_str()),
+            c => escaped.push(c)
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+// Counts are written as strings since they can be far bigger than a JSON number can hold exactly
+pub fn write_json(model: &PopulationModel, series: &[DayCounts], writer: &mut impl Write) -> std::io::Result<()> {
+    writeln!(writer, "[")?;
+    for (index, day) in series.iter().enumerate() {
+        write!(writer, "  {{\"day\": {}, \"total\": \"{}\", \"species\": [", day.day, day.total())?;
+        for (species_index, (species, buckets)) in model.species.iter().zip(day.species.iter()).enumerate() {
+            if species_index > 0 {
+                write!(writer, ", ")?;
+            }
+            let bucket_strs: Vec<String> = buckets.iter().map(|c| format!("\"{}\"", c)).collect();
+            write!(writer, "{{\"name\": {}, \"buckets\": [{}]}}", json_string(species.params.name.as_str()), bucket_strs.join(", "))?;
+        }
+        write!(writer, "]}}")?;
+        writeln!(writer, "{}", if index + 1 < series.len() { "," } else { "" })?;
+    }
+    writeln!(writer, "]")
+}
+
+// Dominant eigenvalue of the transition matrix. Averaging the log of the growth each day means
+// matrices that cycle (no unique dominant direction) still settle on the right rate
+pub fn growth_rate(species: &Species) -> f64 {
+    const WARM_UP: usize = 2000;
+    const SAMPLES: usize = 2000;
+    let matrix = species.transition_matrix();
+    let mut vector = vec![1.0f64; matrix.len()];
+    let mut log_growth = 0.0;
+    for iteration in 0..WARM_UP + SAMPLES {
+        let next: Vec<f64> = matrix.iter().map(|row| row.iter().zip(vector.iter()).map(|(w, v)| *w as f64 * v).sum()).collect();
+        let previous_norm: f64 = vector.iter().sum();
+        let norm: f64 = next.iter().sum();
+        if norm == 0.0 {
+            return 0.0; // everyone died
+        }
+        if iteration >= WARM_UP {
+            log_growth += (norm / previous_norm).ln();
+        }
+        vector = next.iter().map(|v| v / norm).collect();
+    }
+    (log_growth / SAMPLES as f64).exp()
+}
+
+pub struct GrowthReport {
+    // Daily growth factor in the long run. Above 1 means the population grows
+    pub growth_rate: f64,
+    // None when the population isn't growing
+    pub doubling_time: Option<f64>,
+    // Each threshold with the first day the total reached it, if it did within the search limit
+    pub thresholds: Vec<(u128, Option<usize>)>,
+    // The day the counts got too big for a u128, if we got that far. Nothing after it is known
+    pub overflow_day: Option<usize>,
+}
+
+// Gives up on finding a threshold after this many days
+const THRESHOLD_SEARCH_LIMIT: usize = 1_000_000;
+
+pub fn analyse_growth(model: &PopulationModel, thresholds: &[u128]) -> GrowthReport {
+    // The fastest growing species wins out in the end
+    let growth_rate = model.species.iter().map(growth_rate).fold(0.0, f64::max);
+    let doubling_time = if growth_rate > 1.0 { Some(2f64.ln() / growth_rate.ln()) } else { None };
+
+    let matrices: Vec<Vec<Vec<u64>>> = model.species.iter().map(|s| s.transition_matrix()).collect();
+    let mut buckets: Vec<Vec<u128>> = model.species.iter().map(starting_buckets).collect();
+    let mut sorted_thresholds: Vec<u128> = thresholds.to_vec();
+    sorted_thresholds.sort_unstable();
+    let mut crossed = Vec::with_capacity(thresholds.len());
+    let mut day = 0;
+    let mut total: u128 = buckets.iter().flatten().sum();
+    let mut overflow_day = None;
+    for threshold in sorted_thresholds {
+        while total < threshold && total > 0 && day < THRESHOLD_SEARCH_LIMIT && overflow_day.is_none() {
+            let next: Option<Vec<Vec<u128>>> = matrices.iter().zip(buckets.iter())
+                .map(|(matrix, species_buckets)| apply(matrix, species_buckets))
+                .collect();
+            match next {
+                Some(next) => {
+                    buckets = next;
+                    // A total that doesn't fit is past any threshold so saturating is fine here
+                    total = buckets.iter().flatten().fold(0u128, |a, b| a.saturating_add(*b));
+                    day += 1;
+                }
+                None => overflow_day = Some(day + 1)
+            }
+        }
+        let reached = match overflow_day {
+            // More fish than a u128 can count is past any threshold we could be given, and the day
+            // before was still under it
+            Some(overflow_day) if total < threshold => Some(overflow_day),
+            _ if total >= threshold => Some(day),
+            _ => None
+        };
+        crossed.push((threshold, reached));
+    }
+    GrowthReport { growth_rate, doubling_time, thresholds: crossed, overflow_day }
+}
+
+impl GrowthReport {
+    pub fn summary(&self) -> String {
+        let mut summary = format!("Long run growth rate is {:.6} per day\n", self.growth_rate);
+        match self.doubling_time {
+            None => summary.push_str("The population never doubles\n"),
+            Some(days) => summary.push_str(format!("The population doubles every {:.3} days\n", days).as_str())
+        }
+        if let Some(day) = self.overflow_day {
+            summary.push_str(format!("The counts get too big for a u128 on day {}\n", day).as_str());
+        }
+        for (threshold, day) in self.thresholds.iter() {
+            match day {
+                None => summary.push_str(format!("Never reaches {} fish\n", threshold).as_str()),
+                Some(day) => summary.push_str(format!("Reaches {} fish on day {}\n", threshold, day).as_str())
+            }
+        }
+        summary
+    }
+}
@@ -0,0 +1,156 @@
+use crate::bignum::BigUint;
+use crate::population::PopulationModel;
+
+// Counts have to be either exact or wrapped by a modulus since a usize runs out after a few hundred days
+pub trait Count: Clone {
+    // Turns a plain number into a count of the same kind as self
+    fn lift(&self, value: u64) -> Self;
+    fn add(&self, other: &Self) -> Self;
+    fn mul(&self, other: &Self) -> Self;
+}
+
+impl Count for BigUint {
+    fn lift(&self, value: u64) -> Self {
+        BigUint::from_u64(value)
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        BigUint::add(self, other)
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        BigUint::mul(self, other)
+    }
+}
+
+// A count modulo some number, usually a big prime like 1_000_000_007
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModCount {
+    pub value: u64,
+    pub modulus: u64,
+}
+
+impl ModCount {
+    pub fn zero(modulus: u64) -> ModCount {
+        ModCount { value: 0, modulus }
+    }
+}
+
+impl Count for ModCount {
+    fn lift(&self, value: u64) -> Self {
+        ModCount { value: value % self.modulus, modulus: self.modulus }
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        ModCount { value: ((self.value as u128 + other.value as u128) % self.modulus as u128) as u64, modulus: self.modulus }
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        ModCount { value: ((self.value as u128 * other.value as u128) % self.modulus as u128) as u64, modulus: self.modulus }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Matrix<T: Count> {
+    values: Vec<Vec<T>>,
+}
+
+impl<T: Count> Matrix<T> {
+    fn from_u64(values: &[Vec<u64>], zero: &T) -> Matrix<T> {
+        Matrix {
+            values: values.iter().map(|row| row.iter().map(|v| zero.lift(*v)).collect()).collect()
+        }
+    }
+
+    fn identity(n: usize, zero: &T) -> Matrix<T> {
+        let mut values = vec![vec![zero.lift(0); n]; n];
+        for (i, row) in values.iter_mut().enumerate() {
+            row[i] = zero.lift(1);
+        }
+        Matrix { values }
+    }
+
+    fn mul(&self, other: &Matrix<T>, zero: &T) -> Matrix<T> {
+        let n = self.values.len();
+        let mut values = vec![vec![zero.lift(0); n]; n];
+        for (i, row) in values.iter_mut().enumerate() {
+            for k in 0..n {
+                let left = &self.values[i][k];
+                for (j, cell) in row.iter_mut().enumerate() {
+                    *cell = cell.add(&left.mul(&other.values[k][j]));
+                }
+            }
+        }
+        Matrix { values }
+    }
+
+    // Square and multiply so it's O(n³ log days)
+    fn pow(&self, mut exponent: u64, zero: &T) -> Matrix<T> {
+        let mut result = Matrix::identity(self.values.len(), zero);
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.mul(&base, zero);
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = base.mul(&base, zero);
+            }
+        }
+        result
+    }
+}
+
+// Total population `days` from now without stepping through every day
+pub fn population_after<T: Count>(model: &PopulationModel, days: u64, zero: &T) -> T {
+    let mut total = zero.lift(0);
+    for species in model.species.iter() {
+        let transition = Matrix::from_u64(&species.transition_matrix(), zero).pow(days, zero);
+        for row in transition.values.iter() {
+            for (cell, count) in row.iter().zip(species.buckets()) {
+                total = total.add(&cell.mul(&zero.lift(*count as u64)));
+            }
+        }
+    }
+    total
+}
+
+pub fn population_after_exact(model: &PopulationModel, days: u64) -> BigUint {
+    population_after(model, days, &BigUint::default())
+}
+
+pub fn population_after_mod(model: &PopulationModel, days: u64, modulus: u64) -> u64 {
+    population_after(model, days, &ModCount::zero(modulus)).value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::population::SpeciesParams;
+
+    const PRIME: u64 = 1_000_000_007;
+
+    fn models() -> Vec<PopulationModel> {
+        let timers = [3, 4, 3, 1, 2];
+        let mut lanternfish = PopulationModel::new();
+        lanternfish.add_species(SpeciesParams::lanternfish(), &timers).expect("Lanternfish are fine");
+        // Fish that die get bucketed by age, which is the other kind of transition matrix
+        let mut mortal = PopulationModel::new();
+        let params = SpeciesParams { max_age: Some(40), litter_size: 2, ..SpeciesParams::lanternfish() };
+        mortal.add_species(params, &timers).expect("Mortal fish are fine");
+        vec![lanternfish, mortal]
+    }
+
+    #[test]
+    fn fast_forward_matches_stepping() {
+        for model in models() {
+            for days in [0, 1, 80, 256] {
+                let mut stepped = model.clone();
+                stepped.run(days as usize);
+                let expected = stepped.count() as u64;
+                assert_eq!(population_after_exact(&model, days).to_string(), expected.to_string(), "{} days", days);
+                assert_eq!(population_after_mod(&model, days, PRIME), expected % PRIME, "{} days mod {}", days, PRIME);
+            }
+        }
+    }
+}
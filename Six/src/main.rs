@@ -1,60 +1,81 @@
+use std::env;
 use std::fs;
+use std::io;
+use int_list::ListParseError;
+use crate::population::{PopulationModel, SpeciesParams};
 
-fn run_simulation_step(fish_at_each_day: &mut Vec<usize>)  {
-    let num_new_fish = match fish_at_each_day.first() {
-        None => 0,
-        Some(nnf) => *nnf
-    };
-    for i in 1..fish_at_each_day.len() {
-        fish_at_each_day[i-1] = fish_at_each_day[i];
-    }
-    fish_at_each_day[6] += num_new_fish;
-    fish_at_each_day[8] = num_new_fish;
-}
+mod analysis;
+mod bignum;
+mod fast_forward;
+mod population;
 
-fn read_input(filename: &str) -> Option<Vec<u8>> {
+fn read_input(filename: &str) -> Result<Vec<u8>, ListParseError> {
     let file_contents = fs::read_to_string(filename).expect("Couldn't open file");
-    if file_contents.is_empty() {
-        return None;
-    }
-    let mut fish_timers = Vec::new();
-    for val in file_contents.split(",") {
-        match val.parse::<u8>() {
-            Ok(v) => fish_timers.push(v),
-            Err(_) => return None
-        };
-    }
-    Some(fish_timers)
+    int_list::parse_list::<u8>(file_contents.as_str())
 }
 
-fn count_fish(fish_timers: Vec<u8>) -> Vec<usize> {
-    let mut timer_count = Vec::new();
-    timer_count.resize(9,0);
-    for timer in fish_timers {
-        match timer_count.get_mut(timer as usize) {
-            Some(entry) => {
-                *entry += 1;
-            }
-            None => panic!("This shouldn't happen...")
-        }
-    }
-    timer_count
-}
+// Exact counts grow by a digit every few dozen days, so much past this the numbers get too big to
+// multiply in any reasonable time
+const EXACT_DAY_LIMIT: u64 = 100_000;
 
 fn main() {
-    let mut fish_timers = match read_input("input") {
-        None => {
-            panic!("Couldn't read the input");
+    // Six [input] [days] [modulus]
+    // Six [input] series <days> [csv|json]
+    // Six [input] growth [threshold...]
+    let args: Vec<String> = env::args().collect();
+    let filename = args.get(1).map(|f| f.as_str()).unwrap_or("input");
+    let fish_timers = match read_input(filename) {
+        Err(e) => {
+            panic!("Couldn't read the input, {}", e);
         },
-        Some(ft) => ft
+        Ok(ft) => ft
     };
-    let mut timer_count = count_fish(fish_timers);
-    for i in 0..80 {
-        run_simulation_step(&mut timer_count);
+    let mut model = PopulationModel::new();
+    model.add_species(SpeciesParams::lanternfish(), &fish_timers).expect("Couldn't set up the lanternfish");
+
+    match args.get(2).map(|a| a.as_str()) {
+        Some("series") => {
+            let days = args.get(3).map(|d| d.parse::<usize>().expect("Couldn't parse the number of days")).unwrap_or(256);
+            let series = match analysis::time_series(&model, days) {
+                Ok(series) => series,
+                Err(e) => {
+                    println!("Couldn't build the series: {}", e);
+                    return;
+                }
+            };
+            let mut stdout = io::stdout().lock();
+            let result = match args.get(4).map(|f| f.as_str()) {
+                Some("json") => analysis::write_json(&model, &series, &mut stdout),
+                _ => analysis::write_csv(&model, &series, &mut stdout)
+            };
+            result.expect("Couldn't write the series");
+            return;
+        }
+        Some("growth") => {
+            let thresholds: Vec<u128> = args.iter().skip(3).map(|t| t.parse::<u128>().expect("Couldn't parse a threshold")).collect();
+            print!("{}", analysis::analyse_growth(&model, &thresholds).summary());
+            return;
+        }
+        _ => {}
     }
-    println!("There are {} fish after 80 days", timer_count.iter().sum::<usize>());
-    for i in 80..256 {
-        run_simulation_step(&mut timer_count);
+
+    if let Some(days) = args.get(2) {
+        let days = days.parse::<u64>().expect("Couldn't parse the number of days");
+        match args.get(3).map(|m| m.parse::<u64>()) {
+            None if days > EXACT_DAY_LIMIT => {
+                println!("Counting exactly past {} days would take forever, give a modulus as well", EXACT_DAY_LIMIT);
+            }
+            None => println!("There are {} fish after {} days", fast_forward::population_after_exact(&model, days), days),
+            Some(Ok(modulus)) if modulus > 0 => {
+                println!("There are {} fish (mod {}) after {} days", fast_forward::population_after_mod(&model, days, modulus), modulus, days)
+            }
+            Some(_) => panic!("The modulus needs to be a positive number")
+        }
+        return;
     }
-    println!("There are {} fish after 256 days", timer_count.iter().sum::<usize>());
+
+    model.run(80);
+    println!("There are {} fish after 80 days", model.count());
+    model.run(256 - 80);
+    println!("There are {} fish after 256 days", model.count());
 }
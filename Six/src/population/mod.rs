@@ -0,0 +1,189 @@
+// Parameters for one kind of fish. Timers count down to 0 and the fish spawns on the day after its
+// timer reads 0, the same way the lanternfish puzzle describes it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpeciesParams {
+    pub name: String,
+    // Days between spawns once a fish is an adult. Lanternfish reset to 6 so this is 7
+    pub spawn_interval: usize,
+    // Extra days a newborn waits on top of the spawn interval before its first spawn
+    pub newborn_delay: usize,
+    // Fish older than this many days die. None means they live forever
+    pub max_age: Option<usize>,
+    // How many young each fish has per spawn
+    pub litter_size: usize,
+}
+
+impl SpeciesParams {
+    pub fn lanternfish() -> SpeciesParams {
+        SpeciesParams {
+            name: "lanternfish".to_string(),
+            spawn_interval: 7,
+            newborn_delay: 2,
+            max_age: None,
+            litter_size: 1
+        }
+    }
+
+    // The timer a fish starts on when it's born. 8 for lanternfish
+    pub fn newborn_timer(&self) -> usize {
+        self.spawn_interval - 1 + self.newborn_delay
+    }
+
+    // Age in days at which a fish first spawns
+    fn first_spawn_age(&self) -> usize {
+        self.newborn_timer() + 1
+    }
+
+    fn spawns_at_age(&self, age: usize) -> bool {
+        age >= self.first_spawn_age() && (age - self.first_spawn_age()).is_multiple_of(self.spawn_interval)
+    }
+
+    // We only know the timer of the starting fish so they're assumed to be as young as that timer allows
+    fn age_from_timer(&self, timer: usize) -> usize {
+        if timer < self.spawn_interval {
+            self.first_spawn_age() + (self.spawn_interval - 1 - timer)
+        } else {
+            self.newborn_timer() - timer
+        }
+    }
+}
+
+// Immortal fish are bucketed by timer which keeps the buckets small. Once fish can die we have to know
+// how old they are so they get bucketed by age in days instead
+#[derive(Debug, Clone)]
+pub struct Species {
+    pub params: SpeciesParams,
+    buckets: Vec<usize>,
+}
+
+impl Species {
+    pub fn new(params: SpeciesParams, fish_timers: &[u8]) -> Result<Species, String> {
+        if params.spawn_interval == 0 {
+            return Err(format!("{} needs a spawn interval of at least one day", params.name));
+        }
+        let mut species = Species {
+            buckets: vec![0; Species::bucket_count(&params)],
+            params
+        };
+        for timer in fish_timers {
+            let timer = *timer as usize;
+            if timer > species.params.newborn_timer() {
+                return Err(format!("{} can't have a timer of {}, the highest is {}", species.params.name, timer, species.params.newborn_timer()));
+            }
+            let bucket = match species.params.max_age {
+                None => timer,
+                Some(max_age) => {
+                    let age = species.params.age_from_timer(timer);
+                    if age > max_age {
+                        continue; // already dead
+                    }
+                    age
+                }
+            };
+            species.buckets[bucket] += 1;
+        }
+        Ok(species)
+    }
+
+    fn bucket_count(params: &SpeciesParams) -> usize {
+        match params.max_age {
+            None => params.newborn_timer() + 1,
+            Some(max_age) => max_age + 1
+        }
+    }
+
+    pub fn buckets(&self) -> &[usize] {
+        &self.buckets
+    }
+
+    pub fn count(&self) -> usize {
+        self.buckets.iter().sum()
+    }
+
+    // One day of the simulation as a matrix so that tomorrow = matrix * today. Entry [to][from]
+    pub fn transition_matrix(&self) -> Vec<Vec<u64>> {
+        let n = self.buckets.len();
+        let mut matrix = vec![vec![0u64; n]; n];
+        let litter_size = self.params.litter_size as u64;
+        match self.params.max_age {
+            None => {
+                for i in 1..n {
+                    matrix[i - 1][i] = 1;
+                }
+                matrix[self.params.newborn_timer()][0] += litter_size;
+                matrix[self.params.spawn_interval - 1][0] += 1;
+            }
+            Some(_) => {
+                for age in 1..n {
+                    matrix[age][age - 1] = 1;
+                    if self.params.spawns_at_age(age) {
+                        matrix[0][age - 1] += litter_size;
+                    }
+                }
+            }
+        }
+        matrix
+    }
+
+    pub fn step(&mut self) {
+        match self.params.max_age {
+            None => self.step_by_timer(),
+            Some(_) => self.step_by_age()
+        }
+    }
+
+    fn step_by_timer(&mut self) {
+        let num_spawning = self.buckets[0];
+        self.buckets.rotate_left(1);
+        let newborn_timer = self.params.newborn_timer();
+        self.buckets[newborn_timer] = num_spawning * self.params.litter_size;
+        self.buckets[self.params.spawn_interval - 1] += num_spawning;
+    }
+
+    fn step_by_age(&mut self) {
+        // Everyone gets a day older and the oldest fall off the end
+        self.buckets.rotate_right(1);
+        self.buckets[0] = 0;
+        let mut num_born = 0;
+        for (age, count) in self.buckets.iter().enumerate() {
+            if self.params.spawns_at_age(age) {
+                num_born += count * self.params.litter_size;
+            }
+        }
+        self.buckets[0] = num_born;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PopulationModel {
+    pub species: Vec<Species>,
+}
+
+impl PopulationModel {
+    pub fn new() -> PopulationModel {
+        PopulationModel {
+            species: vec![]
+        }
+    }
+
+    pub fn add_species(&mut self, params: SpeciesParams, fish_timers: &[u8]) -> Result<(), String> {
+        self.species.push(Species::new(params, fish_timers)?);
+        Ok(())
+    }
+
+    pub fn step(&mut self) {
+        for species in self.species.iter_mut() {
+            species.step();
+        }
+    }
+
+    pub fn run(&mut self, days: usize) {
+        for _ in 0..days {
+            self.step();
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.species.iter().map(|s| s.count()).sum()
+    }
+}
@@ -0,0 +1,97 @@
+use crate::grammar::{Grammar, Token};
+
+// What's wrong with a line, if anything, and where. Columns count characters from 0
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Opened {
+    pub open: char,
+    pub close: char,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineResult {
+    Complete,
+    // `unclosed` is innermost first, the same order as the completion
+    Incomplete { completion: String, unclosed: Vec<Opened> },
+    // `opener` is None when a closer turns up with nothing open
+    Corrupted { column: usize, found: char, opener: Option<Opened> },
+    Unknown { column: usize, found: char },
+}
+
+pub fn check_line(grammar: &Grammar, line: &str) -> LineResult {
+    let mut open: Vec<Opened> = Vec::new();
+    let mut tokens = grammar.tokens(line);
+    for (column, token) in tokens.by_ref() {
+        match token {
+            Token::Open(c) => {
+                if let Some(close) = grammar.closer_for(c) {
+                    open.push(Opened { open: c, close, column });
+                }
+            }
+            Token::Close(found) => {
+                match open.pop() {
+                    Some(opener) if opener.close == found => {}
+                    opener => return LineResult::Corrupted { column, found, opener }
+                }
+            }
+            Token::Unknown(found) => return LineResult::Unknown { column, found }
+        }
+    }
+    // A quote that never ends gets closed before anything else
+    if let Some((column, quote)) = tokens.open_quote() {
+        open.push(Opened { open: quote.open, close: quote.close, column });
+    }
+    if open.is_empty() {
+        return LineResult::Complete;
+    }
+    open.reverse();
+    LineResult::Incomplete {
+        completion: open.iter().map(|o| o.close).collect(),
+        unclosed: open
+    }
+}
+
+// A line under `line` with marks in the given columns. Tabs are copied across so the marks still line
+// up with what's above them
+fn marker_line(line: &str, marks: &[(usize, char)]) -> String {
+    let last = marks.iter().map(|(column, _)| *column).max().unwrap_or(0);
+    let mut chars = line.chars();
+    (0..=last).map(|column| {
+        let above = chars.next();
+        match marks.iter().find(|(c, _)| *c == column) {
+            Some((_, mark)) => *mark,
+            None if above == Some('\t') => '\t',
+            None => ' '
+        }
+    }).collect()
+}
+
+// The line with a ^ under the problem and a - under the opener it's about, like a compiler error.
+// Columns in the message count from 1
+pub fn explain(line: &str, result: &LineResult) -> Option<String> {
+    let (marks, message) = match result {
+        LineResult::Complete => return None,
+        LineResult::Incomplete { completion, unclosed } => {
+            let innermost = unclosed[0];
+            let others = match unclosed.len() - 1 {
+                0 => String::new(),
+                1 => " and 1 more".to_string(),
+                n => format!(" and {} more", n)
+            };
+            (vec![(innermost.column, '-'), (line.chars().count(), '^')],
+             format!("expected '{}' to close '{}' at col {}{}", completion, innermost.open, innermost.column + 1, others))
+        }
+        LineResult::Corrupted { column, found, opener: None } => {
+            (vec![(*column, '^')], format!("found '{}' with nothing open", found))
+        }
+        LineResult::Corrupted { column, found, opener: Some(opener) } => {
+            (vec![(opener.column, '-'), (*column, '^')],
+             format!("expected '{}' to close '{}' at col {}, found '{}'", opener.close, opener.open, opener.column + 1, found))
+        }
+        LineResult::Unknown { column, found } => {
+            (vec![(*column, '^')], format!("'{}' isn't part of the grammar", found))
+        }
+    };
+    Some(format!("{}\n{} {}", line, marker_line(line, &marks), message))
+}
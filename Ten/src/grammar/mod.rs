@@ -0,0 +1,191 @@
+// Which characters open and close things, which ones don't matter and where brackets stop counting.
+// Grammar files have one rule per line:
+//   pair ( )            an opener and its closer
+//   ignore whitespace   also letters, digits, or any characters listed out like "ignore , ;"
+//   quote " " \         text between the two is skipped, the optional third character escapes the next one
+// Blank lines and lines starting with # are skipped
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quote {
+    pub open: char,
+    pub close: char,
+    pub escape: Option<char>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Grammar {
+    pub pairs: Vec<(char, char)>,
+    pub ignore_whitespace: bool,
+    pub ignore_letters: bool,
+    pub ignore_digits: bool,
+    pub ignored: Vec<char>,
+    pub quotes: Vec<Quote>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    Open(char),
+    Close(char),
+    // Something the grammar doesn't know about
+    Unknown(char),
+}
+
+fn single_char(word: &str) -> Option<char> {
+    let mut chars = word.chars();
+    let c = chars.next()?;
+    if chars.next().is_some() {
+        return None;
+    }
+    Some(c)
+}
+
+impl Grammar {
+    // The four pairs from the puzzle and nothing else
+    pub fn brackets() -> Grammar {
+        Grammar {
+            pairs: vec![('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')],
+            ..Grammar::default()
+        }
+    }
+
+    pub fn parse(definition: &str) -> Result<Grammar, String> {
+        let mut grammar = Grammar::default();
+        for (line_number, line) in definition.lines().enumerate() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let chars: Option<Vec<char>> = words.iter().skip(1).map(|w| single_char(w)).collect();
+            let error = |reason: &str| format!("line {}: {} in \"{}\"", line_number + 1, reason, line);
+            match words.first().copied() {
+                None => {}
+                Some(comment) if comment.starts_with('#') => {}
+                Some("pair") => match chars.as_deref() {
+                    Some([open, close]) => grammar.pairs.push((*open, *close)),
+                    _ => return Err(error("a pair is two characters"))
+                },
+                Some("ignore") => {
+                    for word in words.iter().skip(1) {
+                        match *word {
+                            "whitespace" => grammar.ignore_whitespace = true,
+                            "letters" => grammar.ignore_letters = true,
+                            "digits" => grammar.ignore_digits = true,
+                            _ => match single_char(word) {
+                                None => return Err(error("can only ignore whitespace, letters, digits or single characters")),
+                                Some(c) => grammar.ignored.push(c)
+                            }
+                        }
+                    }
+                }
+                Some("quote") => match chars.as_deref() {
+                    Some([open, close]) => grammar.quotes.push(Quote { open: *open, close: *close, escape: None }),
+                    Some([open, close, escape]) => grammar.quotes.push(Quote { open: *open, close: *close, escape: Some(*escape) }),
+                    _ => return Err(error("a quote is an opening and closing character and maybe an escape"))
+                },
+                Some(_) => return Err(error("rules are pair, ignore or quote"))
+            }
+        }
+        grammar.check()?;
+        Ok(grammar)
+    }
+
+    // Every special character can only mean one thing
+    fn check(&self) -> Result<(), String> {
+        let mut seen: Vec<char> = Vec::new();
+        let mut claim = |c: char| {
+            if seen.contains(&c) {
+                return Err(format!("'{}' is used for more than one thing", c));
+            }
+            seen.push(c);
+            Ok(())
+        };
+        for (open, close) in self.pairs.iter() {
+            claim(*open)?;
+            claim(*close)?;
+        }
+        for quote in self.quotes.iter() {
+            claim(quote.open)?;
+            // Quotes like "" open and close with the same character
+            if quote.close != quote.open {
+                claim(quote.close)?;
+            }
+        }
+        if self.pairs.is_empty() {
+            return Err("there aren't any pairs".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn closer_for(&self, open: char) -> Option<char> {
+        self.pairs.iter().find(|(o, _)| *o == open).map(|(_, close)| *close)
+    }
+
+    pub fn opener_for(&self, close: char) -> Option<char> {
+        self.pairs.iter().find(|(_, c)| *c == close).map(|(open, _)| *open)
+    }
+
+    fn is_ignored(&self, c: char) -> bool {
+        (self.ignore_whitespace && c.is_whitespace())
+            || (self.ignore_letters && c.is_alphabetic())
+            || (self.ignore_digits && c.is_ascii_digit())
+            || self.ignored.contains(&c)
+    }
+
+    pub fn tokens<'a>(&'a self, line: &'a str) -> Tokens<'a> {
+        Tokens {
+            grammar: self,
+            chars: line.chars().enumerate(),
+            quote: None,
+            quote_column: 0,
+            escaped: false
+        }
+    }
+}
+
+// The brackets in a line with their columns, counting from 0. Ignored characters and anything
+// quoted are skipped
+pub struct Tokens<'a> {
+    grammar: &'a Grammar,
+    chars: std::iter::Enumerate<std::str::Chars<'a>>,
+    quote: Option<&'a Quote>,
+    quote_column: usize,
+    escaped: bool,
+}
+
+impl Tokens<'_> {
+    // The quote we're in and the column it started at, if the line stopped here
+    pub fn open_quote(&self) -> Option<(usize, &Quote)> {
+        self.quote.map(|q| (self.quote_column, q))
+    }
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = (usize, Token);
+
+    fn next(&mut self) -> Option<(usize, Token)> {
+        for (column, c) in self.chars.by_ref() {
+            if let Some(quote) = self.quote {
+                if self.escaped {
+                    self.escaped = false;
+                } else if Some(c) == quote.escape {
+                    self.escaped = true;
+                } else if c == quote.close {
+                    self.quote = None;
+                }
+                continue;
+            }
+            if let Some(quote) = self.grammar.quotes.iter().find(|q| q.open == c) {
+                self.quote = Some(quote);
+                self.quote_column = column;
+                continue;
+            }
+            if self.grammar.closer_for(c).is_some() {
+                return Some((column, Token::Open(c)));
+            }
+            if self.grammar.opener_for(c).is_some() {
+                return Some((column, Token::Close(c)));
+            }
+            if !self.grammar.is_ignored(c) {
+                return Some((column, Token::Unknown(c)));
+            }
+        }
+        None
+    }
+}
@@ -1,84 +1,91 @@
-use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufReader;
+use crate::check::LineResult;
+use crate::grammar::Grammar;
+use crate::scoring::Scoring;
 
-fn read_input(filename:&str) -> Vec<String> {
-    match std::fs::read_to_string(filename) {
-        Ok(fc) => fc.lines().map(|l| l.to_string()).collect(),
-        Err(_) => panic!("Couldn't read the input file")
-    }
-}
+mod check;
+mod grammar;
+mod recover;
+mod scoring;
+mod stream;
 
-fn get_opposite(c: char) -> char {
-    return match c {
-        '{' => '}',
-        '}' => '{',
-        '(' => ')',
-        ')' => '(',
-        '[' => ']',
-        ']' => '[',
-        '<' => '>',
-        '>' => '<',
-        _ => ' '
+fn read_input(filename: &str) -> BufReader<File> {
+    match File::open(filename) {
+        Ok(file) => BufReader::new(file),
+        Err(_) => panic!("Couldn't read the input file")
     }
 }
 
-fn get_line_score(line: &String) -> Result<u64,u64> {
-    let mut openers = VecDeque::new();
-    for c in line.chars() {
-        match c {
-            '{' | '(' | '[' | '<' => openers.push_front(c),
-            '}' | ')' | ']' | '>' => {
-                match openers.pop_front() {
-                    None => return Err(char_to_corrupt_score(c)),
-                    Some(opener) => {
-                        if opener != get_opposite(c) {
-                            return Err(char_to_corrupt_score(c));
-                        }
-                    }
-                }
-            }
-            _ => {
-                panic!("O ohh");
-            }
-        }
-    }
-    let mut repair_score:u64 = 0;
-    while let Some(opener) = openers.pop_front() {
-        repair_score = repair_score * 5 + match get_opposite(opener) {
-            ')' => 1,
-            ']' => 2,
-            '}' => 3,
-            '>' => 4,
-            _ => 0
+fn read_config<T>(path: Option<&String>, default: T, parse: fn(&str) -> Result<T, String>) -> T {
+    match path {
+        None => default,
+        Some(path) => {
+            let definition = std::fs::read_to_string(path).unwrap_or_else(|_| panic!("Couldn't read {}", path));
+            parse(&definition).unwrap_or_else(|e| panic!("Couldn't understand {}: {}", path, e))
         }
     }
-    Ok(repair_score)
 }
 
-fn char_to_corrupt_score(c: char) -> u64 {
-    return match c {
-        ')' => 3,
-        ']' => 57,
-        '}' => 1197,
-        '>' => 25137,
-        _ => 0
+fn print_recovery(line_number: usize, recovery: &recover::Recovery) {
+    let edits = match recovery.repairs.len() {
+        1 => "1 edit".to_string(),
+        n => format!("{} edits", n)
+    };
+    if recovery.minimal {
+        println!("Line {} can be fixed with {}:", line_number + 1, edits);
+    } else {
+        println!("Line {} is too long to find the fewest edits, one fix takes {}:", line_number + 1, edits);
+    }
+    for repair in recovery.repairs.iter() {
+        println!("  {}", recover::describe(repair));
     }
 }
 
 fn main() {
-    let input = read_input("input");
+    // Ten [input] [--grammar file] [--scores file] [--explain] [--recover]
+    let args: Vec<String> = std::env::args().collect();
+    let mut input_file = "input";
+    let mut grammar_file = None;
+    let mut scores_file = None;
+    let mut explain = false;
+    let mut recover = false;
+    let mut arg_iter = args.iter().skip(1);
+    while let Some(arg) = arg_iter.next() {
+        match arg.as_str() {
+            "--grammar" => grammar_file = arg_iter.next(),
+            "--scores" => scores_file = arg_iter.next(),
+            "--explain" => explain = true,
+            "--recover" => recover = true,
+            _ => input_file = arg.as_str()
+        }
+    }
+    let grammar = read_config(grammar_file, Grammar::brackets(), Grammar::parse);
+    let scoring = read_config(scores_file, Scoring::puzzle(), Scoring::parse);
+    let input = read_input(input_file);
     let mut corrupt_score = 0;
     let mut repair_scores = Vec::new();
-    for line in &input {
-        match get_line_score(line) {
-            Ok(repair_score) => {
-                repair_scores.push(repair_score);
+    stream::check_lines(input, &grammar, &stream::Chunking::default(), |line_number, line, result| {
+        if explain {
+            if let Some(explanation) = check::explain(line, &result) {
+                println!("Line {}:\n{}", line_number + 1, explanation);
             }
-            Err(line_corrupt_score) => {
-                corrupt_score += line_corrupt_score;
+        }
+        if recover && result != LineResult::Complete {
+            print_recovery(line_number, &recover::recover(&grammar, line));
+        }
+        match result {
+            LineResult::Complete => {}
+            LineResult::Incomplete { completion, .. } => repair_scores.push(scoring.completion_score(&completion)),
+            LineResult::Corrupted { found, .. } => corrupt_score += scoring.corrupt_score(found),
+            LineResult::Unknown { found, .. } => {
+                if !explain {
+                    println!("Line {} has '{}' which the grammar doesn't cover", line_number + 1, found);
+                }
             }
         }
-    }
+    }).expect("Couldn't read the input file");
     repair_scores.sort();
-    let repair_score = repair_scores[repair_scores.len()/2];
+    let repair_score = repair_scores.get(repair_scores.len() / 2).copied().unwrap_or(0);
     println!("The corrupt score is {}, repair score is {}", corrupt_score, repair_score);
 }
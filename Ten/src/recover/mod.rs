@@ -0,0 +1,276 @@
+use crate::check::Opened;
+use crate::grammar::{Grammar, Token};
+
+// Keeps going past the first problem in a line and works out the fewest inserts, deletes and
+// substitutions that would balance it, along with which ones they are. Characters the grammar doesn't
+// know about always have to go so each of those is one edit
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Repair {
+    // A closer taken out
+    Skipped { column: usize, found: char },
+    // A closer put in for an opener that never got one, where the line or the enclosing pair ends
+    Inserted { column: usize, opener: Opened },
+    // Something swapped for the closer the opener wanted
+    Substituted { column: usize, found: char, opener: Opened },
+    // A closer swapped for an opener so it can pair up with something after it
+    Flipped { column: usize, found: char, replacement: char, partner: usize },
+    // Not part of the grammar at all
+    Unknown { column: usize, found: char },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recovery {
+    // In column order
+    pub repairs: Vec<Repair>,
+    // True when the repairs are the fewest that would do. Lines too long to work that out get fixed
+    // left to right instead, which is one way of doing it but maybe not the shortest
+    pub minimal: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Open,
+    Close,
+}
+
+// A bracket from the line along with the pair it belongs to
+#[derive(Debug, Clone, Copy)]
+struct Symbol {
+    column: usize,
+    found: char,
+    kind: Kind,
+    open: char,
+    close: char,
+}
+
+impl Symbol {
+    fn opened(&self) -> Opened {
+        Opened { open: self.open, close: self.close, column: self.column }
+    }
+}
+
+pub fn recover(grammar: &Grammar, line: &str) -> Recovery {
+    let mut repairs = Vec::new();
+    let mut symbols = Vec::new();
+    let mut tokens = grammar.tokens(line);
+    for (column, token) in tokens.by_ref() {
+        let (kind, open, close) = match token {
+            Token::Open(open) => match grammar.closer_for(open) {
+                None => continue,
+                Some(close) => (Kind::Open, open, close)
+            },
+            Token::Close(close) => match grammar.opener_for(close) {
+                None => continue,
+                Some(open) => (Kind::Close, open, close)
+            },
+            Token::Unknown(found) => {
+                repairs.push(Repair::Unknown { column, found });
+                continue;
+            }
+        };
+        let found = if kind == Kind::Open { open } else { close };
+        symbols.push(Symbol { column, found, kind, open, close });
+    }
+    let end = line.chars().count();
+    // A quote that never ends just needs closing
+    if let Some((column, quote)) = tokens.open_quote() {
+        repairs.push(Repair::Inserted { column: end, opener: Opened { open: quote.open, close: quote.close, column } });
+    }
+    let minimal = symbols.len() <= EDIT_DISTANCE_LIMIT;
+    if minimal {
+        repairs.extend(fewest_repairs(&symbols, end));
+    } else {
+        repairs.extend(left_to_right(&symbols, end));
+    }
+    repairs.sort_by_key(|repair| match repair {
+        Repair::Skipped { column, .. } | Repair::Inserted { column, .. } | Repair::Substituted { column, .. }
+            | Repair::Flipped { column, .. } | Repair::Unknown { column, .. } => *column
+    });
+    Recovery { repairs, minimal }
+}
+
+// Working out the fewest edits takes n³ steps so lines with more brackets than this are left alone
+const EDIT_DISTANCE_LIMIT: usize = 400;
+
+// Edits to turn a and b into a matching pair
+fn pair_cost(a: &Symbol, b: &Symbol) -> usize {
+    match (a.kind, b.kind) {
+        (Kind::Open, Kind::Close) if a.close == b.close => 0,
+        (Kind::Open, _) => 1,
+        // a has to become an opener, and it can be whichever one b closes
+        (Kind::Close, Kind::Close) => 1,
+        (Kind::Close, Kind::Open) => 2,
+    }
+}
+
+// The edits that make a into an opener and b into its closer
+fn pair_repairs(a: &Symbol, b: &Symbol, repairs: &mut Vec<Repair>) {
+    match (a.kind, b.kind) {
+        (Kind::Open, Kind::Close) if a.close == b.close => {}
+        (Kind::Open, _) => repairs.push(Repair::Substituted { column: b.column, found: b.found, opener: a.opened() }),
+        (Kind::Close, Kind::Close) => {
+            repairs.push(Repair::Flipped { column: a.column, found: a.found, replacement: b.open, partner: b.column });
+        }
+        (Kind::Close, Kind::Open) => {
+            repairs.push(Repair::Flipped { column: a.column, found: a.found, replacement: a.open, partner: b.column });
+            repairs.push(Repair::Substituted { column: b.column, found: b.found, opener: a.opened() });
+        }
+    }
+}
+
+// Interval DP. best[i][j] is the fewest edits to balance symbols i..j. The first symbol either goes
+// (deleted, or given a new partner, one edit either way) or pairs up with some later symbol k, which
+// splits the rest into the part inside the pair and the part after it. Then we walk back through the
+// choices to get the edits themselves
+fn fewest_repairs(symbols: &[Symbol], end: usize) -> Vec<Repair> {
+    let n = symbols.len();
+    let mut best = vec![vec![0usize; n + 1]; n + 1];
+    for length in 1..=n {
+        for i in 0..=n - length {
+            let j = i + length;
+            let mut cost = best[i + 1][j] + 1;
+            for k in i + 1..j {
+                cost = cost.min(pair_cost(&symbols[i], &symbols[k]) + best[i + 1][k] + best[k + 1][j]);
+            }
+            best[i][j] = cost;
+        }
+    }
+    let mut repairs = Vec::with_capacity(best[0][n]);
+    // Each stretch still to explain, with where a missing closer for it would go
+    let mut stretches = vec![(0, n, end)];
+    while let Some((i, j, closed_at)) = stretches.pop() {
+        if i == j {
+            continue;
+        }
+        let first = &symbols[i];
+        if best[i][j] == best[i + 1][j] + 1 {
+            repairs.push(match first.kind {
+                Kind::Open => Repair::Inserted { column: closed_at, opener: first.opened() },
+                Kind::Close => Repair::Skipped { column: first.column, found: first.found }
+            });
+            stretches.push((i + 1, j, closed_at));
+            continue;
+        }
+        let k = (i + 1..j)
+            .find(|k| best[i][j] == pair_cost(first, &symbols[*k]) + best[i + 1][*k] + best[k + 1][j])
+            .expect("The best cost came from one of the choices");
+        pair_repairs(first, &symbols[k], &mut repairs);
+        stretches.push((i + 1, k, symbols[k].column));
+        stretches.push((k + 1, j, closed_at));
+    }
+    repairs
+}
+
+// For lines too long for the DP. Each time something's wrong we make the smallest fix that lets us
+// carry on
+fn left_to_right(symbols: &[Symbol], end: usize) -> Vec<Repair> {
+    let mut repairs = Vec::new();
+    let mut open: Vec<Opened> = Vec::new();
+    for symbol in symbols {
+        if symbol.kind == Kind::Open {
+            open.push(symbol.opened());
+            continue;
+        }
+        match open.iter().rposition(|o| o.close == symbol.close) {
+            // Whatever's open inside the pair it closes must have been left unclosed
+            Some(depth) => {
+                while open.len() > depth + 1 {
+                    let opener = open.pop().expect("The stack is deeper than depth");
+                    repairs.push(Repair::Inserted { column: symbol.column, opener });
+                }
+                open.pop();
+            }
+            // Doesn't close anything, it was probably meant to close the innermost one
+            None => match open.pop() {
+                None => repairs.push(Repair::Skipped { column: symbol.column, found: symbol.found }),
+                Some(opener) => repairs.push(Repair::Substituted { column: symbol.column, found: symbol.found, opener })
+            }
+        }
+    }
+    while let Some(opener) = open.pop() {
+        repairs.push(Repair::Inserted { column: end, opener });
+    }
+    repairs
+}
+
+// One line per repair, columns counting from 1
+pub fn describe(repair: &Repair) -> String {
+    match repair {
+        Repair::Skipped { column, found } => format!("col {}: removed '{}'", column + 1, found),
+        Repair::Inserted { column, opener } => format!("col {}: inserted '{}' to close '{}' at col {}",
+            column + 1, opener.close, opener.open, opener.column + 1),
+        Repair::Substituted { column, found, opener } => format!("col {}: replaced '{}' with '{}' to close '{}' at col {}",
+            column + 1, found, opener.close, opener.open, opener.column + 1),
+        Repair::Flipped { column, found, replacement, partner } => format!("col {}: replaced '{}' with '{}' to pair with col {}",
+            column + 1, found, replacement, partner + 1),
+        Repair::Unknown { column, found } => format!("col {}: removed '{}' which isn't part of the grammar", column + 1, found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check::{check_line, LineResult};
+
+    // What the line looks like with the repairs made. Closers put in at the same column go innermost
+    // first, and the innermost is the one opened last
+    fn apply(line: &str, repairs: &[Repair]) -> String {
+        let chars: Vec<char> = line.chars().collect();
+        let mut replaced: Vec<Option<char>> = chars.iter().map(|c| Some(*c)).collect();
+        let mut inserted: Vec<Vec<Opened>> = vec![Vec::new(); chars.len() + 1];
+        for repair in repairs {
+            match repair {
+                Repair::Skipped { column, .. } | Repair::Unknown { column, .. } => replaced[*column] = None,
+                Repair::Substituted { column, opener, .. } => replaced[*column] = Some(opener.close),
+                Repair::Flipped { column, replacement, .. } => replaced[*column] = Some(*replacement),
+                Repair::Inserted { column, opener } => inserted[*column].push(*opener),
+            }
+        }
+        let mut fixed = String::new();
+        for (column, closers) in inserted.iter_mut().enumerate() {
+            closers.sort_by_key(|opener| std::cmp::Reverse(opener.column));
+            fixed.extend(closers.iter().map(|opener| opener.close));
+            if let Some(Some(c)) = replaced.get(column) {
+                fixed.push(*c);
+            }
+        }
+        fixed
+    }
+
+    #[test]
+    fn fewest_edits() {
+        let grammar = Grammar::brackets();
+        for (line, edits) in [("(]", 1), ("((]]", 2), (")(", 2), ("(a)", 1), ("", 0), ("([]{})", 0), ("((", 1), ("}", 1), ("[(])", 2)] {
+            let recovery = recover(&grammar, line);
+            assert!(recovery.minimal);
+            assert_eq!(recovery.repairs.len(), edits, "{} got {:?}", line, recovery.repairs);
+        }
+    }
+
+    #[test]
+    fn repairs_balance_the_line() {
+        let grammar = Grammar::brackets();
+        let lines = [
+            "(]", "((]]", ")(", "(a)", "((", "}", "[(])", "])}>([", "(]{{{",
+            "[({(<(())[]>[[{[]{<()<>>",
+            "{([(<{}[<>[]}>{[]{[(<()>",
+            "[[<[([]))<([[{}[[()]]]",
+            "<{([{{}}[<[[[<>{}]]]>[]]",
+        ];
+        for line in lines {
+            let fixed = apply(line, &recover(&grammar, line).repairs);
+            assert_eq!(check_line(&grammar, &fixed), LineResult::Complete, "{} became {}", line, fixed);
+        }
+    }
+
+    #[test]
+    fn long_lines_still_balance() {
+        let grammar = Grammar::brackets();
+        let line = "([{<)]}>".repeat(EDIT_DISTANCE_LIMIT / 4) + "((";
+        let recovery = recover(&grammar, &line);
+        assert!(!recovery.minimal);
+        let fixed = apply(&line, &recovery.repairs);
+        assert_eq!(check_line(&grammar, &fixed), LineResult::Complete);
+    }
+}
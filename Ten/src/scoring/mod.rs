@@ -0,0 +1,64 @@
+// Points for the first bad closer on a corrupted line and for the characters that finish off an
+// incomplete one. Score files have one rule per line:
+//   corrupt ) 3     a corrupted line ending at ) scores 3
+//   complete ) 1    each ) in a completion is worth 1
+//   base 5          the completion score is multiplied by this before each character is added
+// Characters without a score are worth 0. Blank lines and lines starting with # are skipped
+
+#[derive(Debug, Clone)]
+pub struct Scoring {
+    pub corrupt: Vec<(char, u64)>,
+    pub complete: Vec<(char, u64)>,
+    pub base: u64,
+}
+
+fn lookup(table: &[(char, u64)], c: char) -> u64 {
+    table.iter().find(|(t, _)| *t == c).map(|(_, score)| *score).unwrap_or(0)
+}
+
+impl Scoring {
+    pub fn puzzle() -> Scoring {
+        Scoring {
+            corrupt: vec![(')', 3), (']', 57), ('}', 1197), ('>', 25137)],
+            complete: vec![(')', 1), (']', 2), ('}', 3), ('>', 4)],
+            base: 5
+        }
+    }
+
+    pub fn parse(definition: &str) -> Result<Scoring, String> {
+        let mut scoring = Scoring { corrupt: Vec::new(), complete: Vec::new(), base: 5 };
+        for (line_number, line) in definition.lines().enumerate() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let error = |reason: &str| format!("line {}: {} in \"{}\"", line_number + 1, reason, line);
+            let score = |word: &str| word.parse::<u64>().map_err(|_| error("the score has to be a whole number"));
+            match words.as_slice() {
+                [] => {}
+                [comment, ..] if comment.starts_with('#') => {}
+                ["base", base] => scoring.base = score(base)?,
+                [table @ ("corrupt" | "complete"), character, points] => {
+                    let mut chars = character.chars();
+                    let c = match (chars.next(), chars.next()) {
+                        (Some(c), None) => c,
+                        _ => return Err(error("scores are for single characters"))
+                    };
+                    if *table == "corrupt" {
+                        scoring.corrupt.push((c, score(points)?));
+                    } else {
+                        scoring.complete.push((c, score(points)?));
+                    }
+                }
+                _ => return Err(error("rules are corrupt <char> <score>, complete <char> <score> or base <number>"))
+            }
+        }
+        Ok(scoring)
+    }
+
+    pub fn corrupt_score(&self, c: char) -> u64 {
+        lookup(&self.corrupt, c)
+    }
+
+    // Stops at u64::MAX rather than wrapping on really long completions
+    pub fn completion_score(&self, completion: &str) -> u64 {
+        completion.chars().fold(0u64, |score, c| score.saturating_mul(self.base).saturating_add(lookup(&self.complete, c)))
+    }
+}
@@ -0,0 +1,252 @@
+use std::io::BufRead;
+use crate::check::{self, LineResult, Opened};
+use crate::grammar::{Grammar, Token};
+
+// Checks a file a line at a time without reading the whole thing in. Really long lines are cut into
+// chunks that get checked at the same time on their own threads, then the results are stuck back
+// together in order
+
+// When to split a line up and how many pieces to cut it into
+#[derive(Debug, Clone, Copy)]
+pub struct Chunking {
+    // Lines at least this many bytes long get split up
+    pub min_line_length: usize,
+    pub chunks: usize,
+}
+
+// A megabyte before it's worth starting threads, and one chunk per core
+impl Default for Chunking {
+    fn default() -> Self {
+        Chunking {
+            min_line_length: 1 << 20,
+            chunks: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+        }
+    }
+}
+
+// What stopped a chunk. Whatever comes before it can't change that it's wrong
+#[derive(Debug, Clone, Copy)]
+enum Stop {
+    Corrupted { column: usize, found: char, opener: Opened },
+    Unknown { column: usize, found: char },
+}
+
+// A chunk boiled down to what the chunks either side of it need to know. Anything that pairs up inside
+// it has been taken out, which leaves closers for things opened earlier and openers for later
+#[derive(Debug, Clone)]
+struct Summary {
+    // Closers with nothing open in this chunk, in order
+    closers: Vec<(usize, char)>,
+    // Still open at the end of the chunk, outermost first
+    openers: Vec<Opened>,
+    // Nothing after this counts
+    stop: Option<Stop>,
+    // Characters in the chunk, so the next chunk's columns can be moved along
+    length: usize,
+}
+
+fn summarise(grammar: &Grammar, chunk: &str) -> Summary {
+    let mut summary = Summary { closers: Vec::new(), openers: Vec::new(), stop: None, length: chunk.chars().count() };
+    for (column, token) in grammar.tokens(chunk) {
+        match token {
+            Token::Open(c) => {
+                if let Some(close) = grammar.closer_for(c) {
+                    summary.openers.push(Opened { open: c, close, column });
+                }
+            }
+            Token::Close(found) => match summary.openers.pop() {
+                None => summary.closers.push((column, found)),
+                Some(opener) if opener.close == found => {}
+                Some(opener) => {
+                    summary.stop = Some(Stop::Corrupted { column, found, opener });
+                    break;
+                }
+            },
+            Token::Unknown(found) => {
+                summary.stop = Some(Stop::Unknown { column, found });
+                break;
+            }
+        }
+    }
+    summary
+}
+
+impl Summary {
+    // `next` is the chunk straight after this one. Its closers go against our openers, innermost first
+    fn combine(mut self, next: Summary) -> Summary {
+        if self.stop.is_some() {
+            return self;
+        }
+        let offset = self.length;
+        for (column, found) in next.closers {
+            let column = column + offset;
+            match self.openers.pop() {
+                None => self.closers.push((column, found)),
+                Some(opener) if opener.close == found => {}
+                Some(opener) => {
+                    self.stop = Some(Stop::Corrupted { column, found, opener });
+                    return self;
+                }
+            }
+        }
+        self.stop = next.stop.map(|stop| match stop {
+            Stop::Corrupted { column, found, opener } => Stop::Corrupted {
+                column: column + offset,
+                found,
+                opener: Opened { column: opener.column + offset, ..opener }
+            },
+            Stop::Unknown { column, found } => Stop::Unknown { column: column + offset, found }
+        });
+        self.openers.extend(next.openers.into_iter().map(|opener| Opened { column: opener.column + offset, ..opener }));
+        self.length += next.length;
+        self
+    }
+
+    // Closers left over at the start of the line always come before anything else that's wrong
+    fn finish(mut self) -> LineResult {
+        if let Some((column, found)) = self.closers.first() {
+            return LineResult::Corrupted { column: *column, found: *found, opener: None };
+        }
+        match self.stop {
+            Some(Stop::Corrupted { column, found, opener }) => LineResult::Corrupted { column, found, opener: Some(opener) },
+            Some(Stop::Unknown { column, found }) => LineResult::Unknown { column, found },
+            None if self.openers.is_empty() => LineResult::Complete,
+            None => {
+                self.openers.reverse();
+                LineResult::Incomplete {
+                    completion: self.openers.iter().map(|o| o.close).collect(),
+                    unclosed: self.openers
+                }
+            }
+        }
+    }
+}
+
+// Roughly even pieces by bytes, nudged along so none of them starts halfway through a character
+fn split_chunks(line: &str, count: usize) -> Vec<&str> {
+    let mut chunks = Vec::with_capacity(count);
+    let mut rest = line;
+    for remaining in (1..=count).rev() {
+        let mut end = rest.len() / remaining;
+        while !rest.is_char_boundary(end) {
+            end += 1;
+        }
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+fn check_long_line(grammar: &Grammar, line: &str, chunks: usize) -> LineResult {
+    let summaries: Vec<Summary> = std::thread::scope(|scope| {
+        let handles: Vec<_> = split_chunks(line, chunks.max(1)).into_iter()
+            .map(|chunk| scope.spawn(move || summarise(grammar, chunk)))
+            .collect();
+        handles.into_iter().map(|handle| handle.join().expect("Checking a chunk panicked")).collect()
+    });
+    summaries.into_iter().reduce(Summary::combine).map(Summary::finish).unwrap_or(LineResult::Complete)
+}
+
+// A chunk can't tell if it starts inside a quote, so grammars with quotes always go one line at a time
+pub fn check(grammar: &Grammar, line: &str, chunking: &Chunking) -> LineResult {
+    if line.len() < chunking.min_line_length || !grammar.quotes.is_empty() {
+        return check::check_line(grammar, line);
+    }
+    check_long_line(grammar, line, chunking.chunks)
+}
+
+// Calls `each` with the line number, the line without its line ending and what's wrong with it
+pub fn check_lines(mut reader: impl BufRead, grammar: &Grammar, chunking: &Chunking, mut each: impl FnMut(usize, &str, LineResult)) -> std::io::Result<()> {
+    let mut line = String::new();
+    let mut line_number = 0;
+    while reader.read_line(&mut line)? > 0 {
+        let text = line.trim_end_matches(['\n', '\r']);
+        each(line_number, text, check(grammar, text, chunking));
+        line.clear();
+        line_number += 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grammar() -> Grammar {
+        Grammar::parse("pair ( )\npair [ ]\npair { }\npair < >\npair « »\nignore letters").expect("The test grammar is fine")
+    }
+
+    const LINES: [&str; 12] = [
+        "",
+        "[({(<(())[]>[[{[]{<()<>>",
+        "{([(<{}[<>[]}>{[]{[(<()>",
+        "[[<[([]))<([[{}[[()]]]",
+        "<{([{{}}[<[[[<>{}]]]>[]]",
+        "])}>([",
+        "(]{{{",
+        "((a)b)]x(",
+        "(()?]",
+        "«a«é»ü»",
+        "«(é]»",
+        "(«ü»)«",
+    ];
+
+    fn every_boundary(line: &str) -> impl Iterator<Item = usize> + '_ {
+        (0..=line.len()).filter(|i| line.is_char_boundary(*i))
+    }
+
+    #[test]
+    fn two_chunks_match_one_at_every_split() {
+        let grammar = grammar();
+        for line in LINES {
+            let expected = check::check_line(&grammar, line);
+            for split in every_boundary(line) {
+                let (left, right) = line.split_at(split);
+                let combined = summarise(&grammar, left).combine(summarise(&grammar, right)).finish();
+                assert_eq!(combined, expected, "{:?} split at byte {}", line, split);
+            }
+        }
+    }
+
+    #[test]
+    fn three_chunks_match_one_at_every_split() {
+        let grammar = grammar();
+        for line in LINES {
+            let expected = check::check_line(&grammar, line);
+            for first in every_boundary(line) {
+                for second in every_boundary(line).filter(|s| *s >= first) {
+                    let summaries = [&line[..first], &line[first..second], &line[second..]].map(|chunk| summarise(&grammar, chunk));
+                    let combined = summaries.into_iter().reduce(Summary::combine).map(Summary::finish);
+                    assert_eq!(combined, Some(expected.clone()), "{:?} split at bytes {} and {}", line, first, second);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn threads_match_one_chunk() {
+        let grammar = grammar();
+        for line in LINES {
+            let expected = check::check_line(&grammar, line);
+            for chunks in 1..=line.len() + 1 {
+                assert_eq!(check_long_line(&grammar, line, chunks), expected, "{:?} in {} chunks", line, chunks);
+            }
+        }
+    }
+
+    #[test]
+    fn streamed_lines_match() {
+        let grammar = grammar();
+        let input = LINES.join("\r\n");
+        let chunking = Chunking { min_line_length: 0, chunks: 3 };
+        let mut results = Vec::new();
+        check_lines(input.as_bytes(), &grammar, &chunking, |line_number, line, result| {
+            assert_eq!(line, LINES[line_number]);
+            results.push(result);
+        }).expect("Reading from memory can't fail");
+        // A blank first line still counts
+        let expected: Vec<LineResult> = LINES.iter().map(|line| check::check_line(&grammar, line)).collect();
+        assert_eq!(results, expected);
+    }
+}
@@ -1,4 +1,5 @@
 use std::fmt::{Debug, Formatter};
+use grid::Grid;
 
 struct Jellyfish {
     energy_level:u8,
@@ -20,42 +21,7 @@ impl Jellyfish {
     }
 }
 
-struct Grid {
-    data: Vec<Vec<Jellyfish>>,
-    width: usize
-}
-
-impl Grid {
-    fn new() -> Grid {
-        Grid {
-            data: vec![],
-            width: 0
-        }
-    }
-
-    fn insert_row(&mut self, new_row: Vec<Jellyfish>) -> Result<(),()> {
-        if new_row.is_empty() {
-            return Err(());
-        }
-        if self.width == 0 {
-            self.width = new_row.len();
-        }
-        if self.width == new_row.len() {
-            self.data.push(new_row);
-            return Ok(());
-        }
-        Err(())
-    }
-
-    fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut Jellyfish> {
-        if let Some(row) = self.data.get_mut(y) {
-            return row.get_mut(x);
-        }
-        None
-    }
-}
-
-fn increase_power(grid: &mut Grid, x: usize, y: usize, step:usize) -> u32 {
+fn increase_power(grid: &mut Grid<Jellyfish>, x: usize, y: usize, step:usize) -> u32 {
     let jellyfish = match grid.get_mut(x,y) {
         None => return 0,
         Some(jf) => jf
@@ -70,53 +36,34 @@ fn increase_power(grid: &mut Grid, x: usize, y: usize, step:usize) -> u32 {
     let mut flash_count:u32 = 1;
     jellyfish.energy_level = 0;
     jellyfish.last_flashed = step;
-    flash_count += increase_power(grid, x + 1,y, step); // east
-    flash_count += increase_power(grid, x + 1,y + 1, step); // north east
-    flash_count += increase_power(grid, x,y + 1, step); // north
-    if x > 0 {
-        flash_count += increase_power(grid, x - 1,y + 1, step); // north west
-        flash_count += increase_power(grid, x - 1, y, step); // west
-    }
-    if x > 0 && y > 0 {
-        flash_count += increase_power(grid, x - 1,y - 1, step); // south west
-    }
-    if y > 0 {
-        flash_count += increase_power(grid, x,y - 1, step); // south
-        flash_count += increase_power(grid, x + 1,y - 1, step); // south east
+    for (nx, ny) in grid.neighbours8(x, y) {
+        flash_count += increase_power(grid, nx, ny, step);
     }
     flash_count
 }
 
-fn step(grid: &mut Grid, step_number: usize) -> u32 {
+fn step(grid: &mut Grid<Jellyfish>, step_number: usize) -> u32 {
     let mut flash_count = 0;
-    for y in 0..grid.data.len() {
-        for x in 0..grid.width {
-            flash_count += increase_power(grid, x, y, step_number);
-        }
+    for (x, y) in grid.positions() {
+        flash_count += increase_power(grid, x, y, step_number);
     }
     flash_count
 }
 
-fn read_input(filename: &str) -> Grid {
+fn read_input(filename: &str) -> Grid<Jellyfish> {
     let file_contents = match std::fs::read_to_string(filename) {
         Ok(fc) => fc,
         Err(_) => panic!("Couldn't read in the input file")
     };
-    let mut grid = Grid::new();
-    for line in file_contents.lines() {
-        let mut row = Vec::with_capacity(line.len());
-        for c in line.chars() {
-            let energy_level = c.to_digit(10).unwrap_or_else(|| panic!("Couldn't parse the character"));
-            row.push(Jellyfish::new(energy_level as u8));
-        }
-        let _ = grid.insert_row(row);
+    match Grid::parse_digits(&file_contents) {
+        Ok(energy_levels) => energy_levels.map(|energy_level| Jellyfish::new(*energy_level)),
+        Err(e) => panic!("Couldn't parse the input: {}", e)
     }
-    grid
 }
 
 fn main() {
     let mut input = read_input("input");
-    let n_jellyfish = (input.width * input.data.len()) as u32;
+    let n_jellyfish = input.len() as u32;
     let mut flash_count = 0;
     let mut index = 1;
     loop {
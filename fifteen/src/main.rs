@@ -3,17 +3,7 @@ use std::collections::BinaryHeap;
 use std::env;
 use std::fmt::{Display, Formatter};
 use std::time::Instant;
-use ndarray::Array2;
-use strum::IntoEnumIterator;
-use strum_macros::EnumIter;
-
-#[derive(Debug, EnumIter)]
-enum Direction {
-    Left,
-    Right,
-    Up,
-    Down,
-}
+use grid::Grid;
 
 #[derive(Debug, Clone, Copy)]
 struct Node {
@@ -22,9 +12,9 @@ struct Node {
     searched: bool,
 }
 
-impl Default for Node {
-    fn default() -> Self {
-        Node::new(0)
+impl Display for Node {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.risk)
     }
 }
 
@@ -66,31 +56,23 @@ impl Ord for SearchToken {
 }
 
 struct Map {
-    data: ndarray::Array2<Node>,
+    data: Grid<Node>,
 }
 
+// One row per line, each ending in a newline
 impl Display for Map {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         if self.data.is_empty() {
-            return f.write_str("");
+            return Ok(());
         }
-        let mut map_str = String::with_capacity(self.data.len() * self.data.ncols());
-        for row in self.data.rows() {
-            for node in row {
-                map_str.push_str(node.risk.to_string().as_str());
-            }
-            map_str.push('\n');
-        }
-        f.write_str(map_str.as_str())
+        writeln!(f, "{}", self.data)
     }
 }
 
 
 impl Map {
-    fn new(data: Vec<Node>, n_rows: usize, n_cols: usize) -> Self {
-        Map {
-            data: Array2::from_shape_vec((n_rows, n_cols), data).expect("Couldn't create array")
-        }
+    fn new(data: Grid<Node>) -> Self {
+        Map { data }
     }
     fn search(&mut self, start_x: usize, start_y: usize, end_x: usize, end_y: usize) -> Option<usize> {
         let start_node = self.get_mut(start_x, start_y).expect("couldn't get the start node");
@@ -104,107 +86,47 @@ impl Map {
             }
             current_node.searched = true;
             let current_dist = current_node.distance;
-            for direction in Direction::iter() {
-                match self.get_by_direction(closest_token.x, closest_token.y, direction) {
-                    None => {} //nothing in this direction
-                    Some((node, x, y)) => {
-                        if !node.searched && node.distance >= current_dist + node.risk as usize {
-                            node.distance = current_dist + node.risk as usize;
-                            let distance_to_end = (((x as i32 - end_x as i32).pow(2) + (y as i32 - end_y as i32).pow(2)) as f32).sqrt();
-                            let new_token = SearchToken::new(x, y, distance_to_end as u32 + node.distance as u32);
-                            path_q.push(new_token);
-                        }
-                    }
-                };
+            for (x, y) in self.data.neighbours4(closest_token.x, closest_token.y) {
+                let node = &mut self.data[(x, y)];
+                if !node.searched && node.distance >= current_dist + node.risk as usize {
+                    node.distance = current_dist + node.risk as usize;
+                    let distance_to_end = (((x as i32 - end_x as i32).pow(2) + (y as i32 - end_y as i32).pow(2)) as f32).sqrt();
+                    let new_token = SearchToken::new(x, y, distance_to_end as u32 + node.distance as u32);
+                    path_q.push(new_token);
+                }
             }
         }
         None
     }
 
     fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut Node> {
-        self.data.get_mut((y, x))
-    }
-
-    fn get_by_direction(&mut self, current_x: usize, current_y: usize, direction: Direction) -> Option<(&mut Node, usize, usize)> {
-        let mut x = current_x;
-        let mut y = current_y;
-        match direction {
-            Direction::Left => {
-                if current_x <= 0 {
-                    return None;
-                }
-                x -= 1;
-            }
-            Direction::Right => x += 1,
-            Direction::Up => {
-                if current_y <= 0 {
-                    return None;
-                }
-                y -= 1;
-            }
-            Direction::Down => y += 1
-        }
-        self.get_mut(x, y).and_then(|n| Some((n, x, y)))
+        self.data.get_mut(x, y)
     }
 
     fn dimensions(&self) -> (usize, usize) {
-        if self.data.is_empty() {
-            return (0, 0);
-        }
-        (self.data.ncols(), self.data.nrows())
+        (self.data.width(), self.data.height())
     }
 
+    // Repeats the map x times across and y times down. Each repeat to the right or down adds one to
+    // the risk, wrapping 9 back round to 1
     fn tile(&mut self, x: usize, y: usize) {
-        let original_num_rows = self.data.nrows();
-        let original_num_columns = self.data.ncols();
-        let mut new_arr= Array2::<Node>::default((original_num_rows * y, original_num_columns * x));
-        let mut new_arr_iter = new_arr.iter_mut();
-        let mut row_iterator = self.data.rows().into_iter();
-        for v in 0..(original_num_rows * y) {
-            let row = match row_iterator.next() {
-                None => {
-                    row_iterator = self.data.rows().into_iter();
-                    row_iterator.next().expect("couldn't get a row after resetting the iterator")
-                },
-                Some(row) => row
-            };
-            let mut col_iterator = row.iter();
-            for h in 0..original_num_columns * x {
-                let vertical_addition = v / original_num_rows; // int division rounds down
-                let horizontal_addition = h / original_num_columns;
-                let node = match col_iterator.next() {
-                    None => {
-                        col_iterator = row.iter();
-                        col_iterator.next().expect("couldn't get col after resetting the iterator")
-                    }
-                    Some(node) => node
-                };
-                let mut new_risk = node.risk as usize + vertical_addition + horizontal_addition;
-                if new_risk >= 10 {
-                    new_risk = (new_risk % 10) + 1;
-                }
-                new_arr_iter.next().expect("Couldn't get value in new array").risk = new_risk as u8;
+        let width = self.data.width();
+        let height = self.data.height();
+        self.data = Grid::from_fn(width * x, height * y, |h, v| {
+            let node = &self.data[(h % width, v % height)];
+            let mut new_risk = node.risk as usize + v / height + h / width;
+            if new_risk >= 10 {
+                new_risk = (new_risk % 10) + 1;
             }
-
-        }
-        self.data = new_arr;
+            Node::new(new_risk as u8)
+        });
     }
 }
 
 fn read_input(filename: &str) -> Map {
     let file_contents = std::fs::read_to_string(filename).expect("couldn't read file");
-    let mut data = Vec::new();
-    let mut width = 0;
-    let mut row_count = 0;
-    for line in file_contents.lines() {
-        for c in line.chars() {
-            let parsed = c.to_digit(10).expect("couldn't parse character to digit") as u8;
-            data.push(Node::new(parsed));
-        }
-        width = line.len();
-        row_count += 1;
-    }
-    Map::new(data, row_count, width)
+    let risks = Grid::parse_digits(&file_contents).expect("couldn't parse character to digit");
+    Map::new(risks.map(|risk| Node::new(*risk)))
 }
 
 fn problem_one() {
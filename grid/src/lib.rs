@@ -0,0 +1,377 @@
+use std::fmt::{Display, Formatter};
+use std::iter::StepBy;
+use std::slice::{Chunks, ChunksMut, Iter, IterMut};
+
+// A rectangular grid stored row by row. x is the column and y is the row, with (0, 0) in the top left
+// like the puzzle inputs
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GridErrorKind {
+    // The row isn't as wide as the ones before it
+    Ragged { expected: usize, found: usize },
+    BadCell(char),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridError {
+    pub row: usize,
+    pub column: usize,
+    pub kind: GridErrorKind,
+}
+
+impl Display for GridError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            GridErrorKind::Ragged { expected, found } => write!(f, "row {} has {} cells but the rows before it have {}", self.row + 1, found, expected),
+            GridErrorKind::BadCell(c) => write!(f, "can't read '{}' at row {} column {}", c, self.row + 1, self.column + 1)
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    data: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+// Offsets to the cells next door, the four straight ones first
+const OFFSETS: [(i64, i64); 8] = [(1, 0), (0, 1), (-1, 0), (0, -1), (1, 1), (-1, 1), (-1, -1), (1, -1)];
+
+// The positions around a cell that are inside the grid. It only holds the size so the grid can still
+// be changed while going through them
+pub struct Neighbours {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    offsets: &'static [(i64, i64)],
+}
+
+impl Iterator for Neighbours {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        while let Some(((dx, dy), rest)) = self.offsets.split_first() {
+            self.offsets = rest;
+            let x = self.x as i64 + dx;
+            let y = self.y as i64 + dy;
+            if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+                return Some((x as usize, y as usize));
+            }
+        }
+        None
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Grid<T> {
+        Grid {
+            data: vec![value; width * height],
+            width,
+            height
+        }
+    }
+
+    // Keeps the left `width` columns
+    pub fn truncate_columns(&mut self, width: usize) {
+        if width >= self.width {
+            return;
+        }
+        let mut data = Vec::with_capacity(width * self.height);
+        for row in self.data.chunks(self.width) {
+            data.extend_from_slice(&row[..width]);
+        }
+        self.data = data;
+        self.width = width;
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(usize, usize) -> T) -> Grid<T> {
+        let mut data = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                data.push(f(x, y));
+            }
+        }
+        Grid { data, width, height }
+    }
+
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Grid<T>, GridError> {
+        let width = rows.first().map(|row| row.len()).unwrap_or(0);
+        let height = rows.len();
+        let mut data = Vec::with_capacity(width * height);
+        for (y, row) in rows.into_iter().enumerate() {
+            if row.len() != width {
+                return Err(GridError { row: y, column: 0, kind: GridErrorKind::Ragged { expected: width, found: row.len() } });
+            }
+            data.extend(row);
+        }
+        Ok(Grid { data, width, height })
+    }
+
+    // One row per line and one cell per character. Blank lines are skipped
+    pub fn parse_with(input: &str, mut parse_cell: impl FnMut(char) -> Option<T>) -> Result<Grid<T>, GridError> {
+        let mut rows = Vec::new();
+        for line in input.lines().filter(|line| !line.trim().is_empty()) {
+            let mut row = Vec::with_capacity(line.len());
+            for (x, c) in line.trim_end().chars().enumerate() {
+                match parse_cell(c) {
+                    None => return Err(GridError { row: rows.len(), column: x, kind: GridErrorKind::BadCell(c) }),
+                    Some(value) => row.push(value)
+                }
+            }
+            rows.push(row);
+        }
+        Grid::from_rows(rows)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn in_bounds(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if !self.in_bounds(x, y) {
+            return None;
+        }
+        self.data.get(y * self.width + x)
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if !self.in_bounds(x, y) {
+            return None;
+        }
+        self.data.get_mut(y * self.width + x)
+    }
+
+    // Returns false if (x, y) is outside the grid
+    pub fn set(&mut self, x: usize, y: usize, value: T) -> bool {
+        match self.get_mut(x, y) {
+            None => false,
+            Some(cell) => {
+                *cell = value;
+                true
+            }
+        }
+    }
+
+    pub fn neighbours4(&self, x: usize, y: usize) -> Neighbours {
+        Neighbours { x, y, width: self.width, height: self.height, offsets: &OFFSETS[..4] }
+    }
+
+    // Diagonals included
+    pub fn neighbours8(&self, x: usize, y: usize) -> Neighbours {
+        Neighbours { x, y, width: self.width, height: self.height, offsets: &OFFSETS }
+    }
+
+    pub fn row(&self, y: usize) -> Option<&[T]> {
+        if y >= self.height {
+            return None;
+        }
+        Some(&self.data[y * self.width..(y + 1) * self.width])
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> Option<&mut [T]> {
+        if y >= self.height {
+            return None;
+        }
+        Some(&mut self.data[y * self.width..(y + 1) * self.width])
+    }
+
+    pub fn column(&self, x: usize) -> Option<StepBy<Iter<'_, T>>> {
+        if x >= self.width {
+            return None;
+        }
+        Some(self.data[x..].iter().step_by(self.width))
+    }
+
+    pub fn column_mut(&mut self, x: usize) -> Option<StepBy<IterMut<'_, T>>> {
+        if x >= self.width {
+            return None;
+        }
+        let width = self.width;
+        Some(self.data[x..].iter_mut().step_by(width))
+    }
+
+    pub fn rows(&self) -> Chunks<'_, T> {
+        self.data.chunks(self.width.max(1))
+    }
+
+    pub fn rows_mut(&mut self) -> ChunksMut<'_, T> {
+        self.data.chunks_mut(self.width.max(1))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.data.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        self.data.iter_mut()
+    }
+
+    // Every (x, y) in reading order. It doesn't hold on to the grid so cells can be changed along the way
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.positions().zip(self.data.iter())
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            data: self.data.iter().map(f).collect(),
+            width: self.width,
+            height: self.height
+        }
+    }
+
+    // Keeps the top `height` rows
+    pub fn truncate_rows(&mut self, height: usize) {
+        if height >= self.height {
+            return;
+        }
+        self.data.truncate(height * self.width);
+        self.height = height;
+    }
+}
+
+impl Grid<u8> {
+    // The usual puzzle input where every cell is a single digit
+    pub fn parse_digits(input: &str) -> Result<Grid<u8>, GridError> {
+        Grid::parse_with(input, |c| c.to_digit(10).map(|d| d as u8))
+    }
+}
+
+impl<T> std::ops::Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        self.get(x, y).unwrap_or_else(|| panic!("({}, {}) is outside a {}x{} grid", x, y, self.width, self.height))
+    }
+}
+
+impl<T> std::ops::IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        let (width, height) = (self.width, self.height);
+        self.get_mut(x, y).unwrap_or_else(|| panic!("({}, {}) is outside a {}x{} grid", x, y, width, height))
+    }
+}
+
+// Each cell's own Display with no gaps, one line per row
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (y, row) in self.rows().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGITS: &str = "2199943210\n3987894921\n9856789892\n8767896789\n9899965678";
+
+    fn sorted(neighbours: Neighbours) -> Vec<(usize, usize)> {
+        let mut positions: Vec<(usize, usize)> = neighbours.collect();
+        positions.sort_unstable();
+        positions
+    }
+
+    #[test]
+    fn get_outside_is_none() {
+        let mut grid = Grid::parse_digits(DIGITS).unwrap();
+        assert_eq!((grid.width(), grid.height()), (10, 5));
+        assert_eq!(grid.get(0, 0), Some(&2));
+        assert_eq!(grid.get(9, 4), Some(&8));
+        assert_eq!(grid.get(10, 0), None);
+        assert_eq!(grid.get(0, 5), None);
+        assert_eq!(grid.get(usize::MAX, usize::MAX), None);
+        assert_eq!(grid.get_mut(10, 4), None);
+        assert_eq!(grid.get_mut(9, 5), None);
+        *grid.get_mut(9, 4).unwrap() = 0;
+        assert_eq!(grid[(9, 4)], 0);
+        assert!(!grid.set(10, 0, 1));
+    }
+
+    #[test]
+    fn neighbours_stay_inside() {
+        let grid = Grid::new(3, 2, 0);
+        assert_eq!(sorted(grid.neighbours4(0, 0)), vec![(0, 1), (1, 0)]);
+        assert_eq!(sorted(grid.neighbours8(0, 0)), vec![(0, 1), (1, 0), (1, 1)]);
+        assert_eq!(sorted(grid.neighbours4(2, 1)), vec![(1, 1), (2, 0)]);
+        assert_eq!(sorted(grid.neighbours8(2, 1)), vec![(1, 0), (1, 1), (2, 0)]);
+        // The middle of the top edge
+        assert_eq!(sorted(grid.neighbours4(1, 0)), vec![(0, 0), (1, 1), (2, 0)]);
+        assert_eq!(sorted(grid.neighbours8(1, 0)), vec![(0, 0), (0, 1), (1, 1), (2, 0), (2, 1)]);
+        let big = Grid::new(3, 3, 0);
+        assert_eq!(big.neighbours4(1, 1).count(), 4);
+        assert_eq!(big.neighbours8(1, 1).count(), 8);
+        // The straight ones come first
+        assert!(big.neighbours8(1, 1).take(4).all(|(x, y)| x == 1 || y == 1));
+        let single = Grid::new(1, 1, 0);
+        assert_eq!(single.neighbours8(0, 0).count(), 0);
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        let mut grid = Grid::parse_digits(DIGITS).unwrap();
+        assert_eq!(grid.row(1), Some(&[3, 9, 8, 7, 8, 9, 4, 9, 2, 1][..]));
+        assert_eq!(grid.row(5), None);
+        assert_eq!(grid.column(2).unwrap().copied().collect::<Vec<u8>>(), vec![9, 8, 5, 6, 9]);
+        assert!(grid.column(10).is_none());
+        grid.row_mut(0).unwrap()[1] = 0;
+        for cell in grid.column_mut(9).unwrap() {
+            *cell = 7;
+        }
+        assert_eq!(grid.row(0).unwrap(), &[2, 0, 9, 9, 9, 4, 3, 2, 1, 7]);
+        assert_eq!(grid.column(9).unwrap().copied().collect::<Vec<u8>>(), vec![7; 5]);
+        assert_eq!(grid.rows().count(), 5);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Grid::parse_digits("123\n4x6"),
+            Err(GridError { row: 1, column: 1, kind: GridErrorKind::BadCell('x') }));
+        assert_eq!(Grid::parse_digits("123\n45\n789"),
+            Err(GridError { row: 1, column: 0, kind: GridErrorKind::Ragged { expected: 3, found: 2 } }));
+        assert_eq!(Grid::parse_digits("12\n345"),
+            Err(GridError { row: 1, column: 0, kind: GridErrorKind::Ragged { expected: 2, found: 3 } }));
+        // Trailing whitespace and blank lines don't count
+        assert_eq!(Grid::parse_digits("12 \n\n34\n").unwrap().row(1), Some(&[3, 4][..]));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let grid = Grid::parse_digits(DIGITS).unwrap();
+        assert_eq!(grid.to_string(), DIGITS);
+        assert_eq!(Grid::parse_digits(&grid.to_string()), Ok(grid));
+    }
+}
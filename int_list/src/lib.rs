@@ -0,0 +1,188 @@
+use std::fmt::{Display, Formatter};
+
+// Parses comma separated integer lists like "3,4,3,1,2". Values can also be split across lines,
+// surrounding whitespace is ignored and a value can be a range. "3..7" gives 3,4,5,6 and "3..=7"
+// includes the 7
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListParseErrorKind {
+    Empty,
+    NotANumber,
+    OutOfRange,
+    BackwardsRange,
+    RangeTooLong,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListParseError {
+    // Byte offset of the bad token in the input
+    pub offset: usize,
+    pub token: String,
+    pub kind: ListParseErrorKind,
+}
+
+impl Display for ListParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let reason = match self.kind {
+            ListParseErrorKind::Empty => "missing a value",
+            ListParseErrorKind::NotANumber => "not a number",
+            ListParseErrorKind::OutOfRange => "out of range",
+            ListParseErrorKind::BackwardsRange => "range ends before it starts",
+            ListParseErrorKind::RangeTooLong => "range has too many values",
+        };
+        write!(f, "{} at offset {}: \"{}\"", reason, self.offset, self.token)
+    }
+}
+
+impl std::error::Error for ListParseError {}
+
+struct Token<'a> {
+    text: &'a str,
+    offset: usize,
+    // Blank lines and trailing commas are fine but a,,b and leading commas aren't
+    allowed_empty: bool,
+}
+
+fn tokenise(input: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    for (index, c) in input.char_indices().chain(std::iter::once((input.len(), '\n'))) {
+        if c != ',' && c != '\n' {
+            continue;
+        }
+        let raw = &input[start..index];
+        let text = raw.trim();
+        let offset = start + (raw.len() - raw.trim_start().len());
+        tokens.push(Token {
+            text,
+            offset,
+            allowed_empty: c == '\n'
+        });
+        start = index + c.len_utf8();
+    }
+    tokens
+}
+
+fn parse_number<T: TryFrom<i128>>(text: &str, token: &Token) -> Result<T, ListParseError> {
+    let error = |kind| ListParseError {
+        offset: token.offset,
+        token: token.text.to_string(),
+        kind
+    };
+    let value = text.trim().parse::<i128>().map_err(|e| match e.kind() {
+        std::num::IntErrorKind::PosOverflow | std::num::IntErrorKind::NegOverflow => error(ListParseErrorKind::OutOfRange),
+        std::num::IntErrorKind::Empty => error(ListParseErrorKind::Empty),
+        _ => error(ListParseErrorKind::NotANumber)
+    })?;
+    T::try_from(value).map_err(|_| error(ListParseErrorKind::OutOfRange))
+}
+
+// A range with more values than this is almost certainly a typo, and would take gigabytes to hold
+pub const MAX_RANGE_LENGTH: i128 = 1 << 24;
+
+fn push_range<T: TryFrom<i128>>(values: &mut Vec<T>, start: &str, end: &str, inclusive: bool, token: &Token) -> Result<(), ListParseError> {
+    let error = |kind| ListParseError {
+        offset: token.offset,
+        token: token.text.to_string(),
+        kind
+    };
+    let start: i128 = parse_number(start, token)?;
+    let mut end: i128 = parse_number(end, token)?;
+    if inclusive {
+        end = end.checked_add(1).ok_or_else(|| error(ListParseErrorKind::OutOfRange))?;
+    }
+    if end < start {
+        return Err(error(ListParseErrorKind::BackwardsRange));
+    }
+    if end.checked_sub(start).is_none_or(|length| length > MAX_RANGE_LENGTH) {
+        return Err(error(ListParseErrorKind::RangeTooLong));
+    }
+    for value in start..end {
+        values.push(T::try_from(value).map_err(|_| error(ListParseErrorKind::OutOfRange))?);
+    }
+    Ok(())
+}
+
+pub fn parse_list<T: TryFrom<i128>>(input: &str) -> Result<Vec<T>, ListParseError> {
+    let mut values = Vec::new();
+    for token in tokenise(input) {
+        if token.text.is_empty() {
+            if token.allowed_empty {
+                continue;
+            }
+            return Err(ListParseError {
+                offset: token.offset,
+                token: String::new(),
+                kind: ListParseErrorKind::Empty
+            });
+        }
+        if let Some((start, end)) = token.text.split_once("..=") {
+            push_range(&mut values, start, end, true, &token)?;
+        } else if let Some((start, end)) = token.text.split_once("..") {
+            push_range(&mut values, start, end, false, &token)?;
+        } else {
+            values.push(parse_number(token.text, &token)?);
+        }
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_at(input: &str) -> (usize, String, ListParseErrorKind) {
+        let error = parse_list::<i64>(input).expect_err("The input should be rejected");
+        (error.offset, error.token, error.kind)
+    }
+
+    #[test]
+    fn plain_list() {
+        assert_eq!(parse_list::<u8>("3,4,3,1,2"), Ok(vec![3, 4, 3, 1, 2]));
+    }
+
+    #[test]
+    fn bad_token_offsets() {
+        assert_eq!(error_at("1,2,x,4"), (4, "x".to_string(), ListParseErrorKind::NotANumber));
+        assert_eq!(error_at("1,  12a ,3"), (4, "12a".to_string(), ListParseErrorKind::NotANumber));
+        assert_eq!(error_at("1,2\n3,,4"), (6, String::new(), ListParseErrorKind::Empty));
+        assert_eq!(error_at(",1"), (0, String::new(), ListParseErrorKind::Empty));
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse_list::<i64>("3..7"), Ok(vec![3, 4, 5, 6]));
+        assert_eq!(parse_list::<i64>("3..=7"), Ok(vec![3, 4, 5, 6, 7]));
+        assert_eq!(parse_list::<i64>("1,-2..1,9"), Ok(vec![1, -2, -1, 0, 9]));
+        assert_eq!(parse_list::<i64>("3..3"), Ok(vec![]));
+        assert_eq!(parse_list::<i64>("3..=3"), Ok(vec![3]));
+        assert_eq!(error_at("1,7..3").2, ListParseErrorKind::BackwardsRange);
+        assert_eq!(error_at("7..=5").2, ListParseErrorKind::BackwardsRange);
+        assert_eq!(error_at("0..=4000000000").2, ListParseErrorKind::RangeTooLong);
+    }
+
+    #[test]
+    fn trailing_commas_and_newlines() {
+        assert_eq!(parse_list::<u8>("1,2,"), Ok(vec![1, 2]));
+        assert_eq!(parse_list::<u8>("1,2\n"), Ok(vec![1, 2]));
+        assert_eq!(parse_list::<u8>("1,2,\n3,4,\n\n"), Ok(vec![1, 2, 3, 4]));
+        assert_eq!(parse_list::<u8>(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn surrounding_whitespace() {
+        assert_eq!(parse_list::<u8>("  1 ,\t2 , 3  \r\n"), Ok(vec![1, 2, 3]));
+        assert_eq!(parse_list::<i64>(" 1 ..= 3 "), Ok(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn out_of_range_for_the_type() {
+        assert_eq!(parse_list::<u8>("255"), Ok(vec![255]));
+        let error = parse_list::<u8>("1,256").expect_err("256 doesn't fit in a u8");
+        assert_eq!((error.offset, error.kind), (2, ListParseErrorKind::OutOfRange));
+        assert_eq!(parse_list::<u8>("-1").map_err(|e| e.kind), Err(ListParseErrorKind::OutOfRange));
+        assert_eq!(parse_list::<u8>("250..=256").map_err(|e| e.kind), Err(ListParseErrorKind::OutOfRange));
+        assert_eq!(parse_list::<i128>("170141183460469231731687303715884105728").map_err(|e| e.kind), Err(ListParseErrorKind::OutOfRange));
+        assert_eq!(parse_list::<i128>("0..=170141183460469231731687303715884105727").map_err(|e| e.kind), Err(ListParseErrorKind::OutOfRange));
+    }
+}
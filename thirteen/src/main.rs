@@ -1,82 +1,56 @@
 use std::env;
 use std::fmt::{Display, Formatter};
 use std::time::Instant;
-use crate::table::{Table, TableCell, TableIterator, TableIteratorType, IteratorDirection};
+use grid::Grid;
 
-mod table;
+// Dots on the paper. Folding moves every dot past the line onto its mirror image and cuts the paper
+// down to what's left of the line or above it
+struct Paper {
+    dots: Grid<bool>
+}
 
-impl<T: Copy> Table<T> {
-    fn fold(&mut self, mut src_iter: TableIterator, mut dst_iter: TableIterator) {
-        loop {
-            let src_value:T = match src_iter.next(self) {
-                None => break, // There is no more to iterate over
-                Some(src_value) => {
-                    match src_value {
-                        TableCell::Null => { // empty cell so we skip it on the destination side
-                            if !dst_iter.skip(self) {
-                                break; // no more space on the destination
-                            }
-                            continue; // We have skipped here so we need to go back to the start of the loop
-                        },
-                        TableCell::Some(src_value) => *src_value
-                    }
-                }
-            };
-            if !dst_iter.update(self, src_value) {
-                break;
-            }
-        }
-    }
+impl Paper {
     fn fold_x(&mut self, x: usize) {
-        if x + 1 >= self.num_columns() {
-            // Can't fold along a row that doesn't exist or the last row
+        if x + 1 >= self.dots.width() {
+            // Can't fold along a column that doesn't exist or the last column
             return;
         }
-        let src_iter = table::TableIterator::new(x+1, IteratorDirection::Forward, TableIteratorType::Column);
-        let dst_iter = table::TableIterator::new(x-1, IteratorDirection::Backward, TableIteratorType::Column);
-
-        self.fold(src_iter, dst_iter);
-        self.truncate_by_column(x);
+        for y in 0..self.dots.height() {
+            for src_x in x + 1..self.dots.width() {
+                // Anything that folds past the left edge falls off
+                match (2 * x).checked_sub(src_x) {
+                    Some(dst_x) if self.dots[(src_x, y)] => self.dots[(dst_x, y)] = true,
+                    _ => {}
+                }
+            }
+        }
+        self.dots.truncate_columns(x);
     }
 
     fn fold_y(&mut self, y: usize) {
-        if y + 1 >= self.num_rows() {
+        if y + 1 >= self.dots.height() {
             // Can't fold along a row that doesn't exist or the last row
             return;
         }
-        let src_iter = table::TableIterator::new(y+1, IteratorDirection::Forward, TableIteratorType::Row);
-        let dst_iter = table::TableIterator::new(y-1, IteratorDirection::Backward, TableIteratorType::Row);
-
-        self.fold(src_iter, dst_iter);
-        self.truncate_by_row(y);
-    }
-
-    fn count_non_null_cells(&self) -> usize {
-        let mut non_null:usize = 0;
-        for cell in self.cell_iterator() {
-            match cell {
-                TableCell::Null => {}
-                TableCell::Some(_) => non_null += 1
+        for src_y in y + 1..self.dots.height() {
+            for x in 0..self.dots.width() {
+                match (2 * y).checked_sub(src_y) {
+                    Some(dst_y) if self.dots[(x, src_y)] => self.dots[(x, dst_y)] = true,
+                    _ => {}
+                }
             }
         }
-        non_null
+        self.dots.truncate_rows(y);
+    }
+
+    fn count_dots(&self) -> usize {
+        self.dots.iter().filter(|dot| **dot).count()
     }
 }
 
-impl Display for table::Table<bool> {
+impl Display for Paper {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let mut table_string = String::with_capacity(self.len() + self.num_rows());
-        for row in self.row_iterator() {
-            for column in row {
-                match column {
-                    TableCell::Null => table_string.push(' '),
-                    TableCell::Some(_) => {table_string.push('█')}
-                }
-            }
-            table_string.push('\n');
-        }
-        table_string.pop();//pop final new line
-        f.write_str(table_string.as_str())
+        self.dots.map(|dot| if *dot { '█' } else { ' ' }).fmt(f)
     }
 }
 
@@ -85,7 +59,7 @@ enum Fold {
     Y(usize)
 }
 
-fn read_input(filename: &str) -> (Table<bool>, Vec<Fold>) {
+fn read_input(filename: &str) -> (Paper, Vec<Fold>) {
     let file_contents = match std::fs::read_to_string(filename) {
         Ok(fc) => fc,
         Err(_) => panic!("Couldn't read the input")
@@ -108,9 +82,9 @@ fn read_input(filename: &str) -> (Table<bool>, Vec<Fold>) {
         max_x = std::cmp::max(x+1, max_x);
         max_y = std::cmp::max(y+1, max_y);
     }
-    let mut table = Table::with_capacity(max_x, max_x * max_y);
+    let mut dots = Grid::new(max_x, max_y, false);
     for (x, y) in points {
-        table.set_cell(x,y,true);
+        dots[(x, y)] = true;
     }
     let mut instructions = Vec::new();
     for line in line_iterator {
@@ -127,26 +101,26 @@ fn read_input(filename: &str) -> (Table<bool>, Vec<Fold>) {
             panic!("Couldn't parse instruction dimension");
         }
     }
-    (table, instructions)
+    (Paper { dots }, instructions)
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let now = Instant::now();
-    let (mut table, instructions) = read_input(if args.len() < 2 {"input"} else {args[1].as_str()});
+    let (mut paper, instructions) = read_input(if args.len() < 2 {"input"} else {args[1].as_str()});
     let mut dots_after_first = 0;
     let mut first = true;
     for instruction in instructions {
         match instruction {
-            Fold::X(i) => table.fold_x(i),
-            Fold::Y(i) => table.fold_y(i),
+            Fold::X(i) => paper.fold_x(i),
+            Fold::Y(i) => paper.fold_y(i),
         }
         if first {
-            dots_after_first = table.count_non_null_cells();
+            dots_after_first = paper.count_dots();
             first = false;
         }
 
     }
-    println!("After the first fold there are {} dots. After all the folds there are {} dots which looks like\n{}", dots_after_first, table.count_non_null_cells(), table);
+    println!("After the first fold there are {} dots. After all the folds there are {} dots which looks like\n{}", dots_after_first, paper.count_dots(), paper);
     println!("time taken was {} seconds", now.elapsed().as_secs_f64());
 }